@@ -1,37 +1,89 @@
-use iced::{Alignment, Background, Border, Color, Element, Length, Subscription, Task, 
-    widget::{button, checkbox, column, container, row, scrollable, text, text_editor, text_input}};
-use rfd::FileDialog;
+use iced::{Alignment, Background, Border, Color, Element, Length, Padding, Subscription, Task, task,
+    widget::{Space, button, checkbox, column, container, pick_list, row, scrollable, text, text_editor, text_input, tooltip}};
+use rfd::{AsyncMessageDialog, FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use unicode_width::UnicodeWidthChar;
 use std::time::{Duration, Instant};
 use rand::Rng;
+use rand::seq::SliceRandom;
+use serde_json::json;
+use tokio::sync::Semaphore;
 
-use crate::twitch_utils::{Bot, create_bots};
+use crate::control_api::{self, ApiEvent, ApiRequest, ApiResponse};
+use crate::helix::{self, TokenInfo};
+use crate::hotkeys::{Hotkey, HotkeyAction};
+use crate::messages::{MessageEntry, find_blocked, mask_blocked, parse_blocklist, parse_messages_csv, parse_messages_json, write_messages_csv, write_messages_json};
+use crate::presets::{self, SendPreset};
+use crate::schedule::{ScheduledMessage, next_time_at};
+use crate::session::{BotStats, SessionStats};
+use crate::history::{ChatEntry, ChatLog, EntryKind, export_history, set_max_history};
+use crate::logging;
+use crate::settings::Settings;
+use crate::theme::{Palette, Theme};
+use crate::twitch_utils::{
+    Bot, BotCredentials, BotStatus, BurstLimiter, Delivery, LoginCheck, MAX_MESSAGE_LENGTH, action_payload, action_text, is_line_config, is_valid_login, load_bots, message_too_long,
+    MAX_TIMEOUT_MS, Timeouts, is_rejected_command, parse_channels, split_message, test_irc_connection, unsupported_command,
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     None, 
 
-    LoadedMessages(String),
-    LoadedConfig(String),
-    BotChecked(usize, bool),
-    ToggleBotEnabled(usize, bool),
+    LoadedMessages(Vec<String>),
+    LoadedMessageEntries(Vec<MessageEntry>),
+    MessagesLoadFailed(String),
+    LoadedConfig(PathBuf, String),
+    ToggleNormalizeLogins(bool),
+    ToggleTls(bool),
+    ToggleTheme,
+    StopAll,
+    /// The login round trip and welcomed name, `None` if the bot couldn't
+    /// log in, plus what the token lookup found.
+    BotChecked(u64, Option<LoginCheck>, Option<TokenInfo>),
+    ToggleBotEnabled(u64, bool),
+    SetAllBotsEnabled(bool),
+    InvertBotsEnabled,
+    SortBots(SortKey),
+    ToggleBotModerator(u64),
+    BotChannelUpdated(u64, String),
     
     LoadMessagesPress,
+    SaveMessagesPress,
+    MessagesSaved(Result<PathBuf, String>),
     LoadConfigPress,
     CheckBotsPress,
+    RemoveDeadBots,
+    ConfirmRemoveDeadBots,
+    /// The token file to rewrite and the dead tokens to drop from it.
+    RemoveDeadTokensFromFile(PathBuf, HashSet<String>),
     
     MessageUpdated(String),
     ChannelNameUpdated(String),
     
-    MessageSent(usize, Result<(), String>),
+    /// The bot and send it is for, then how it went out or the final
+    /// error.
+    MessageSent(u64, u64, Result<Delivery, String>),
     
-    SendMessage(usize),
+    SendMessage(u64),
+    SendMessageSingle,
     SendMessageAllBots,
     SendMessageRandomBot,
     
     ToggleRandomMessages(bool),
     MinIntervalUpdated(String),
     MaxIntervalUpdated(String),
+    BotCooldownUpdated(String),
+    MaxConcurrentChecksUpdated(String),
+    TimeoutUpdated(TimeoutField, String),
+    ResetTimeouts,
+    ToggleAdvanced,
+    OpenLogFile,
     Tick(Instant),
+    PollMentions,
     SendRandomMessage,
     SendRandomMessageNow,
     
@@ -44,34 +96,450 @@ pub enum Message {
     MultipleBotsCountUpdated(String),
     
     ToggleClearAfterSend(bool),
+    ToggleAutoSplit(bool),
+    ToggleNumberSplitParts(bool),
+    ToggleRejectUnknownCommands(bool),
     
     MessagesEditorAction(text_editor::Action),
+    ToggleEditorLinePicking(bool),
+    SendSelectedLine,
+    SaveMessageAsDefault,
+
+    ToggleChannelMatrix,
+    ToggleStats,
+    SortStats(StatsSort),
+    UndoLastSend,
+    /// A moderator bot's delete of one of its undone messages finished.
+    UndoDeleted(u64, Result<(), String>),
+    ToggleConfirmMassSends(bool),
+    ConfirmMassSend(MassSend),
+    CancelMassSend,
+    LoadBlocklistPress,
+    LoadedBlocklist(String),
+    ToggleMaskBlocked(bool),
+    ToggleNoRepeats(bool),
+    ResetStats,
+    ChannelMatrixAction(text_editor::Action),
+    ToggleChannelMatrixAllBots(bool),
+    SendChannelMatrix,
     
-    ToggleBotChatView(usize),
+    ToggleBotChatView(u64),
     CloseBotChatView,
     BotMessageUpdated(String),
-    SendBotMessage(usize),
-    ClearBotHistory(usize),
+    SendBotMessage(u64),
+    CancelPendingSends(u64),
+    SendToTestChannel(u64),
+    WhisperTargetUpdated(String),
+    SendWhisper(u64),
+    /// The bot, the whispered text to give back if it failed, and the result.
+    WhisperSent(u64, String, Result<(), String>),
+    TestChannelUpdated(String),
+    /// Saves settings typed into a field once it is submitted; the window
+    /// closing saves them too.
+    SaveSettings,
+    ClearBotHistory(u64),
+    ExportHistoryPress,
+    ExportBotHistory(u64),
+    HistoryExported(Result<PathBuf, String>),
     ClearGlobalHistory,
     ClearAllHistory,
     
     SearchQueryUpdated(String),
+    ChatSearchUpdated(String),
+    ChatScrolled(scrollable::Viewport),
     
-    MessageClicked(usize),
+    MessageClicked(u64),
+    CopyChatEntry(u64),
+    DeleteHistoryEntry(u64),
+
+    HotkeyPressed(Hotkey),
+    ToggleHotkeySettings,
+    StartRebindHotkey(HotkeyAction),
+    ClearHotkey(HotkeyAction),
+
+    SingleSendPolicyChanged(SingleSendPolicy),
+    SelectActiveBot(u64),
+
+    RevealToken(u64),
+    ConfirmRevealToken(u64),
+    HideToken,
+
+    ToggleBurstLimit(bool),
+    MaxRetriesUpdated(String),
+    BurstMaxMessagesUpdated(String),
+    BurstWindowUpdated(String),
+
+    ToggleShowSummaryOnExit(bool),
+    WindowCloseRequested(iced::window::Id),
+
+    ToggleShowBots(bool),
+    ToggleShowEditor(bool),
+    ToggleShowChat(bool),
+
+    ToggleControlApi(bool),
+    ControlApiPortUpdated(String),
+    ApplyControlApiPort,
+    RegenerateControlApiToken,
+    ControlApi(ApiEvent),
+
+    ToggleRequireLive(bool),
+    LiveStatusChecked(String, Result<bool, String>),
+
+    PresetNameUpdated(String),
+    SavePreset(String),
+    ApplyPreset(String),
+
+    ToggleTokenTester,
+    TesterTokenUpdated(String),
+    TestToken,
+    TokenTested(Result<TokenTestReport, String>),
+    AddTestedBot,
+
+    ToggleSequenceEditor,
+    ToggleSchedule,
+    ScheduleTimeUpdated(String),
+    ScheduleTextUpdated(String),
+    ScheduleBotSelected(BotChoice),
+    ScheduleRepeatUpdated(String),
+    AddSchedule,
+    RemoveSchedule(usize),
+    SequenceEditorAction(text_editor::Action),
+    SequenceGapUpdated(String),
+    StartSequence,
+    StopSequence,
+    SequenceStep(u64),
+    /// A delayed send was called off before it went out.
+    SendDropped(u64, u64),
+}
+
+/// Sends started for one bot that haven't reported back yet, keyed by
+/// send so each is forgotten when it finishes.
+#[derive(Default)]
+struct PendingSends {
+    handles: HashMap<u64, task::Handle>,
+    /// The history entries and burst slots of each send, rolled back if it
+    /// is called off before going out.
+    logged: HashMap<u64, (HashSet<u64>, usize)>,
+}
+
+/// One bot's send, ready to go out: the parts for each channel it goes to.
+struct StagedSend {
+    bot_id: u64,
+    action: u64,
+    credentials: BotCredentials,
+    payloads: Vec<(String, Vec<String>)>,
+    entries: HashSet<u64>,
+    slots: usize,
+}
+
+/// What one user action sent, e.g. a click, a hotkey, an API call or a
+/// whole sequence run, so undo can take it back as one.
+#[derive(Debug, Default)]
+struct SendAction {
+    id: u64,
+    /// History entries the action logged.
+    entries: HashSet<u64>,
+    /// Sends by key, with their bot and how they ended once they report
+    /// back, so undo can take back their counts.
+    results: HashMap<u64, (u64, Option<Result<(), String>>)>,
+    /// `(bot, channel, id)` of each message Twitch confirmed, which a
+    /// moderator bot can delete again.
+    message_ids: Vec<(u64, String, String)>,
+}
+
+impl SendAction {
+    /// Makes `action` the one undo takes back. Entries logged under the
+    /// same action, like the steps of one sequence run, pile up; a newer
+    /// action starts over.
+    fn log(&mut self, action: u64, entries: impl IntoIterator<Item = u64>) {
+        if self.id != action {
+            *self = SendAction { id: action, ..SendAction::default() };
+        }
+        self.entries.extend(entries);
+    }
+
+    /// Starts waiting on a bot's send, unless a newer action took over
+    /// while it was being staged.
+    fn track(&mut self, action: u64, send: u64, bot_id: u64) {
+        if self.id == action {
+            self.results.insert(send, (bot_id, None));
+        }
+    }
+
+    /// Records how a tracked send went and the messages it left in chat.
+    fn report(&mut self, send: u64, result: &Result<Delivery, String>) {
+        let Some((bot_id, outcome)) = self.results.get_mut(&send) else {
+            return;
+        };
+        *outcome = Some(result.as_ref().map(|_| ()).map_err(Clone::clone));
+        if let Ok(delivery) = result {
+            let bot_id = *bot_id;
+            self.message_ids.extend(
+                delivery.message_ids.iter().map(|(channel, message_id)| (bot_id, channel.clone(), message_id.clone()))
+            );
+        }
+    }
+
+    /// Lets go of a send that never went out, with its history lines.
+    fn forget(&mut self, send: u64, entries: &HashSet<u64>) {
+        self.results.remove(&send);
+        self.entries.retain(|entry| !entries.contains(entry));
+    }
+}
+
+/// Lets mode changes call off sends that are still waiting out their
+/// per-bot delay.
+#[derive(Debug, Clone, Default)]
+struct DelayGate {
+    generation: Arc<AtomicU64>,
+    waiting: Arc<AtomicUsize>,
+}
+
+/// Counts a send as waiting until dropped, so aborted sends are let go too.
+struct Waiting(Arc<AtomicUsize>);
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl DelayGate {
+    /// Sleeps for `delay`, then says whether the send should still go out.
+    async fn wait(self, delay: Duration) -> bool {
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _waiting = Waiting(self.waiting.clone());
+        async_std::task::sleep(delay).await;
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Drops every send still waiting and returns how many there were.
+    fn cancel(&self) -> usize {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.waiting.load(Ordering::SeqCst)
+    }
+}
+
+/// An ordered list of (bot id, message) steps being played back one at a time.
+#[derive(Debug)]
+struct SequenceRun {
+    generation: u64,
+    /// Every step belongs to the one action that started the run.
+    action: u64,
+    steps: Vec<(u64, String)>,
+    next: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenTestReport {
+    token: String,
+    info: TokenInfo,
+    irc_ok: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingleSendPolicy {
+    FirstAvailable,
+    RandomAvailable,
+    LeastRecentlyUsed,
+    RoundRobin,
+    SelectedBot,
+}
+
+impl SingleSendPolicy {
+    const ALL: [SingleSendPolicy; 5] = [
+        SingleSendPolicy::FirstAvailable,
+        SingleSendPolicy::RandomAvailable,
+        SingleSendPolicy::LeastRecentlyUsed,
+        SingleSendPolicy::RoundRobin,
+        SingleSendPolicy::SelectedBot,
+    ];
+}
+
+impl fmt::Display for SingleSendPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SingleSendPolicy::FirstAvailable => "First available",
+            SingleSendPolicy::RandomAvailable => "Random available",
+            SingleSendPolicy::LeastRecentlyUsed => "Least recently used",
+            SingleSendPolicy::RoundRobin => "Round-robin",
+            SingleSendPolicy::SelectedBot => "Selected bot",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Availability,
+    Enabled,
+    MessageCount,
+}
+
+impl SortKey {
+    const ALL: [SortKey; 4] = [
+        SortKey::Name,
+        SortKey::Availability,
+        SortKey::Enabled,
+        SortKey::MessageCount,
+    ];
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortKey::Name => "Name",
+            SortKey::Availability => "Available first",
+            SortKey::Enabled => "Enabled first",
+            SortKey::MessageCount => "Most messages",
+        })
+    }
+}
+
+/// The column the stats panel ranks bots by, highest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsSort {
+    #[default]
+    Total,
+    Sent,
+    Failed,
+    Received,
+}
+
+impl StatsSort {
+    const ALL: [StatsSort; 4] = [
+        StatsSort::Total,
+        StatsSort::Sent,
+        StatsSort::Failed,
+        StatsSort::Received,
+    ];
+
+    fn value(self, stats: &BotStats) -> usize {
+        match self {
+            StatsSort::Total => stats.total(),
+            StatsSort::Sent => stats.sent,
+            StatsSort::Failed => stats.failed,
+            StatsSort::Received => stats.received,
+        }
+    }
+}
+
+impl fmt::Display for StatsSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StatsSort::Total => "Most sends",
+            StatsSort::Sent => "Most sent",
+            StatsSort::Failed => "Most failed",
+            StatsSort::Received => "Most mentions",
+        })
+    }
+}
+
+/// A send from many bots that may have to be confirmed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MassSend {
+    AllBots,
+    RandomNow,
+    ChannelMatrix,
+}
+
+impl MassSend {
+    /// The message that starts this send again once it is confirmed.
+    fn message(self) -> Message {
+        match self {
+            MassSend::AllBots => Message::SendMessageAllBots,
+            MassSend::RandomNow => Message::SendRandomMessageNow,
+            MassSend::ChannelMatrix => Message::SendChannelMatrix,
+        }
+    }
+}
+
+/// A pick-list entry for the bot a scheduled message goes out from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotChoice {
+    id: Option<u64>,
+    name: String,
+}
+
+impl fmt::Display for BotChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(_) => f.write_str(&self.name),
+            None => f.write_str("Any available bot"),
+        }
+    }
+}
+
+/// One of the network waits in the advanced settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutField {
+    Check,
+    Login,
+    Join,
+    PartGap,
+    Notice,
+    RetryBackoff,
+}
+
+impl TimeoutField {
+    const ALL: [TimeoutField; 6] = [
+        TimeoutField::Check,
+        TimeoutField::Login,
+        TimeoutField::Join,
+        TimeoutField::PartGap,
+        TimeoutField::Notice,
+        TimeoutField::RetryBackoff,
+    ];
+
+    fn slot(self, timeouts: &mut Timeouts) -> &mut u64 {
+        match self {
+            TimeoutField::Check => &mut timeouts.check_ms,
+            TimeoutField::Login => &mut timeouts.login_ms,
+            TimeoutField::Join => &mut timeouts.join_ms,
+            TimeoutField::PartGap => &mut timeouts.part_gap_ms,
+            TimeoutField::Notice => &mut timeouts.notice_ms,
+            TimeoutField::RetryBackoff => &mut timeouts.retry_backoff_ms,
+        }
+    }
+}
+
+impl fmt::Display for TimeoutField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimeoutField::Check => "Bot check",
+            TimeoutField::Login => "Login",
+            TimeoutField::Join => "Join",
+            TimeoutField::PartGap => "Between parts",
+            TimeoutField::Notice => "Refusal wait",
+            TimeoutField::RetryBackoff => "Retry backoff",
+        })
+    }
 }
 
 pub struct App {
     message: String,
     bots: Vec<Bot>,
-    chat_history: Vec<String>,
+    chat_history: ChatLog,
     channel: String,
     messages: Vec<String>,
+    /// 1-based numbers of the `messages` lines over the length limit,
+    /// worked out when the list changes rather than on every redraw.
+    long_lines: Vec<usize>,
+    message_meta: HashMap<String, MessageEntry>,
     
     random_messages_enabled: bool,
     min_interval: u64,
     max_interval: u64,
+    /// Seconds a bot sits out random sends after posting.
+    bot_cooldown: u64,
     next_message_time: Option<Instant>,
     last_message_time: Option<Instant>,
+
+    tokens_path: Option<PathBuf>,
+    normalize_logins: bool,
+    pending_checks: usize,
+    bots_checked: bool,
     
     all_bots_mode: bool,
     simultaneous_mode: bool,
@@ -82,28 +550,136 @@ pub struct App {
     multiple_bots_count: usize,
     
     clear_after_send: bool,
+    /// Text taken out of the input by the latest send, and the bots whose
+    /// sends have yet to report back.
+    cleared_message: Option<(String, HashSet<u64>)>,
+    auto_split: bool,
+    number_split_parts: bool,
+    reject_unknown_commands: bool,
     
     messages_editor: text_editor::Content,
+    /// Deal each message once per cycle instead of picking at random.
+    no_repeats: bool,
+    /// Messages left in the current cycle, drawn from the back.
+    message_bag: Vec<String>,
+    last_bag_message: Option<String>,
+    editor_truncated: bool,
+    /// Where the message list was last loaded from or saved to.
+    messages_path: Option<PathBuf>,
+    editor_picks_message: bool,
+    selected_editor_line: Option<usize>,
+    loading_messages: bool,
     
-    viewing_bot_chat: Option<usize>,
+    viewing_bot_chat: Option<u64>,
     bot_message_input: String,
+    whisper_target: String,
     
     search_query: String,
+    chat_search_query: String,
+    /// Scroll offset and height of the global chat, for picking which
+    /// rows to build.
+    chat_viewport: (f32, f32),
+
+    show_hotkey_settings: bool,
+    rebinding_hotkey: Option<HotkeyAction>,
+
+    single_send_policy: SingleSendPolicy,
+    /// Last sort applied to the bot list, shown in its picker.
+    bot_sort: Option<SortKey>,
+    /// Index the round-robin policy tries next.
+    round_robin_cursor: usize,
+    selected_bot: Option<u64>,
+
+    revealed_token: Option<u64>,
+
+    /// Keyed by bot id.
+    pending_sends: HashMap<u64, PendingSends>,
+    next_send: u64,
+
+    burst_limiter: BurstLimiter,
+    delay_gate: DelayGate,
+    max_send_retries: u32,
+    burst_holding: bool,
+
+    session: SessionStats,
+    show_summary_on_exit: bool,
+
+
+    control_api_enabled: bool,
+    control_api_port: u16,
+    /// Port as typed; only applied on Enter so the listener isn't rebound per keystroke.
+    control_api_port_input: String,
+    control_api_token: String,
+
+    require_live_channels: HashSet<String>,
+    live_status: HashMap<String, (Option<bool>, Instant)>,
+    live_check_pending: HashSet<String>,
+    live_holding: bool,
+
+    presets: BTreeMap<String, SendPreset>,
+    preset_name: String,
+    active_preset: Option<String>,
+
+    show_token_tester: bool,
+    tester_token: String,
+    tester_running: bool,
+    tester_result: Option<Result<TokenTestReport, String>>,
+
+    show_sequence_editor: bool,
+    /// Clock-time sends, soonest first.
+    schedules: Vec<ScheduledMessage>,
+    show_schedule: bool,
+    show_advanced: bool,
+    schedule_time: String,
+    schedule_text: String,
+    schedule_bot: Option<u64>,
+    /// Minutes between repeats; empty sends once.
+    schedule_repeat: String,
+    sequence_editor: text_editor::Content,
+    sequence_gap: u64,
+    sequence_run: Option<SequenceRun>,
+    sequence_generation: u64,
+
+    settings: Settings,
+
+    show_channel_matrix: bool,
+    /// The latest send action, for undo.
+    last_action: SendAction,
+    next_action: u64,
+    /// Undone sends that haven't reported back; they aren't counted.
+    undone_sends: HashSet<u64>,
+    /// A mass send the user just confirmed, which goes out without asking
+    /// again.
+    mass_send_confirmed: Option<MassSend>,
+    /// Per-bot send counts for the stats panel; unlike the session
+    /// summary these can be reset.
+    bot_stats: HashMap<u64, BotStats>,
+    show_stats: bool,
+    stats_sort: StatsSort,
+    channel_matrix_editor: text_editor::Content,
+    channel_matrix_all_bots: bool,
 }
 
 impl App {
     pub fn new() -> (Self, Task<Message>) {
-        let app = Self {
+        let mut app = Self {
             bots: Vec::new(),
-            chat_history: Vec::new(),
+            chat_history: ChatLog::default(),
             message: String::new(),
             channel: String::new(),
             messages: Vec::new(),
+            long_lines: Vec::new(),
+            message_meta: HashMap::new(),
             random_messages_enabled: false,
             min_interval: 30,
+            bot_cooldown: 0,
             max_interval: 120,
             next_message_time: None,
             last_message_time: None,
+            tokens_path: None,
+            normalize_logins: true,
+            pending_checks: 0,
+            bots_checked: false,
             all_bots_mode: false,
             simultaneous_mode: true,
             min_bot_delay: 1,
@@ -111,12 +687,99 @@ impl App {
             multiple_bots_mode: false,
             multiple_bots_count: 3,
             clear_after_send: false,
+            cleared_message: None,
+            auto_split: true,
+            number_split_parts: false,
+            reject_unknown_commands: true,
             messages_editor: text_editor::Content::new(),
+            no_repeats: false,
+            message_bag: Vec::new(),
+            last_bag_message: None,
+            editor_truncated: false,
+            messages_path: None,
+            editor_picks_message: false,
+            selected_editor_line: None,
+            loading_messages: false,
             viewing_bot_chat: None,
             bot_message_input: String::new(),
+            whisper_target: String::new(),
             search_query: String::new(),
+            chat_search_query: String::new(),
+            chat_viewport: (0.0, 800.0),
+            show_hotkey_settings: false,
+            rebinding_hotkey: None,
+            single_send_policy: SingleSendPolicy::FirstAvailable,
+            bot_sort: None,
+            round_robin_cursor: 0,
+            selected_bot: None,
+            revealed_token: None,
+            pending_sends: HashMap::new(),
+            next_send: 0,
+            burst_limiter: BurstLimiter::new(100, 30),
+            delay_gate: DelayGate::default(),
+            max_send_retries: 3,
+            burst_holding: false,
+            session: SessionStats::new(),
+            show_summary_on_exit: false,
+            control_api_enabled: false,
+            control_api_port: 8787,
+            control_api_port_input: "8787".to_string(),
+            control_api_token: generate_api_token(),
+            require_live_channels: HashSet::new(),
+            live_status: HashMap::new(),
+            live_check_pending: HashSet::new(),
+            live_holding: false,
+            presets: BTreeMap::new(),
+            preset_name: String::new(),
+            active_preset: None,
+            show_token_tester: false,
+            tester_token: String::new(),
+            tester_running: false,
+            tester_result: None,
+            show_sequence_editor: false,
+            schedules: Vec::new(),
+            show_schedule: false,
+            show_advanced: false,
+            schedule_time: String::new(),
+            schedule_text: String::new(),
+            schedule_bot: None,
+            schedule_repeat: String::new(),
+            sequence_editor: text_editor::Content::new(),
+            sequence_gap: 2,
+            sequence_run: None,
+            sequence_generation: 0,
+            settings: Settings::default(),
+            show_channel_matrix: false,
+            bot_stats: HashMap::new(),
+            last_action: SendAction::default(),
+            next_action: 0,
+            undone_sends: HashSet::new(),
+            mass_send_confirmed: None,
+            show_stats: false,
+            stats_sort: StatsSort::default(),
+            channel_matrix_editor: text_editor::Content::new(),
+            channel_matrix_all_bots: false,
         };
 
+        match Settings::load() {
+            Ok(settings) => {
+                app.message = settings.default_message.clone();
+                app.channel = settings.channel.clone();
+                if let Some(send) = settings.send.clone() {
+                    app.apply_preset(send);
+                }
+                set_max_history(settings.max_history);
+                app.settings = settings;
+                app.settings.timeouts = app.settings.timeouts.clamped();
+            },
+            Err(e) => app.chat_history.push(ChatEntry::error(format!("Failed to load settings: {}", e))),
+        }
+
+        match presets::load_presets() {
+            Ok(presets) => app.presets = presets,
+            Err(e) => app.chat_history.push(ChatEntry::error(format!("Failed to load presets: {}", e))),
+        }
+
         return (app, Task::none())
     }
 
@@ -124,17 +787,18 @@ impl App {
         match message {
             Message::MessageUpdated(message) => {
                 self.message = message;
+                self.cleared_message = None;
                 Task::none()
             },
             Message::LoadConfigPress => {
                 let file = FileDialog::new()
-                    .add_filter("Text Document", &["txt"]);
+                    .add_filter("Bot config", &["txt", "json", "toml"]);
     
                 if let Some(path) = file.pick_file() {
                     Task::perform(
                         async move {
-                            match std::fs::read_to_string(path) {
-                                Ok(content) => Message::LoadedConfig(content),
+                            match std::fs::read_to_string(&path) {
+                                Ok(content) => Message::LoadedConfig(path, content),
                                 Err(_) => Message::None,
                             }
                         },
@@ -144,25 +808,115 @@ impl App {
                     Task::none()
                 }
             },
-            Message::LoadedConfig(content) => {
-                self.bots = create_bots(&content);
+            Message::LoadedConfig(path, content) => {
+                self.bots = match load_bots(&path, &content) {
+                    Ok(bots) => bots,
+                    Err(e) => {
+                        self.chat_history.push(ChatEntry::error(format!(
+                            "Failed to read {}: {}",
+                            path.display(),
+                            e
+                        )));
+                        return Task::none();
+                    }
+                };
+                if self.normalize_logins {
+                    self.bots.iter_mut().for_each(Bot::normalize_login);
+                }
+
+                let invalid: Vec<&str> = self.bots
+                    .iter()
+                    .filter(|bot| !is_valid_login(&bot.login))
+                    .map(|bot| bot.login.as_str())
+                    .collect();
+                if !invalid.is_empty() {
+                    self.chat_history.push(ChatEntry::info(format!(
+                        "⚠️ {} bots have logins Twitch won't accept: {}",
+                        invalid.len(),
+                        invalid.join(", ")
+                    )));
+                }
+
+                self.selected_bot = None;
+                self.tokens_path = Some(path);
+                self.pending_sends.clear();
+                self.bots_checked = false;
+                self.sequence_run = None;
+                Task::none()
+            },
+            Message::ToggleNormalizeLogins(enabled) => {
+                self.normalize_logins = enabled;
+                Task::none()
+            },
+            Message::ToggleTls(enabled) => {
+                self.settings.use_tls = enabled;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::StopAll => {
+                self.random_messages_enabled = false;
+                self.next_message_time = None;
+                self.live_holding = false;
+                self.sequence_run = None;
+
+                // Aborting drops sends still waiting on their delay or retry
+                // before they write anything.
+                let pending: Vec<u64> = self.pending_sends.keys().copied().collect();
+                let cancelled: usize = self.pending_sends.values().map(|pending| pending.handles.len()).sum();
+                for id in pending {
+                    self.cancel_pending_sends(id);
+                }
+                for bot in &mut self.bots {
+                    bot.set_enabled(false);
+                }
+
+                self.chat_history.push(ChatEntry::info(format!(
+                    "🛑 Emergency stop: random messages off, {} pending sends cancelled, all bots disabled",
+                    cancelled
+                )));
+                Task::none()
+            },
+            Message::ToggleTheme => {
+                self.settings.theme = self.settings.theme.toggled();
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
                 Task::none()
             },
             Message::CheckBotsPress => {
                 let mut tasks: Vec<Task<Message>> = Vec::new();
+                self.pending_checks = self.bots.len();
+                for bot in &mut self.bots {
+                    bot.status = BotStatus::Checking;
+                }
+                // All tasks start at once; the semaphore lets only so many
+                // connect at a time.
+                let permits = Arc::new(Semaphore::new(self.settings.max_concurrent_checks.max(1)));
 
-                for (index, bot) in self.bots.iter().enumerate() {
-                    let bot_clone = bot.clone();
+                for bot in &self.bots {
+                    let id = bot.id;
+                    let mut credentials = bot.credentials(self.settings.timeouts, self.settings.use_tls);
+                    let permits = permits.clone();
         
                     tasks.push(
                         Task::perform(
                             async move {
-                                bot_clone.test_connection().await
+                                let _permit = permits.acquire_owned().await?;
+                                // A failed lookup shouldn't fail a bot that can log in,
+                                // so the IRC check still runs with the login we have.
+                                let info = credentials.validate_token().await.ok();
+                                if let Some(info) = &info {
+                                    credentials.login = info.login.clone();
+                                }
+                                let check = credentials.test_connection().await?;
+                                anyhow::Ok((check, info))
                             },
                             move |result| {
                                 match result {
-                                    Ok(is_valid) => Message::BotChecked(index, is_valid),
-                                    Err(_) => Message::BotChecked(index, false),
+                                    Ok((check, info)) => Message::BotChecked(id, check, info),
+                                    Err(_) => Message::BotChecked(id, None, None),
                                 }
                             }
                         )
@@ -171,51 +925,191 @@ impl App {
 
                 Task::batch(tasks)
             },
-            Message::BotChecked(index, flag) => {
-                if let Some(bot) = self.bots.get_mut(index) {
-                    bot.set_available(flag);
+            Message::BotChecked(id, check, info) => {
+                if let Some(index) = self.bot_index(id) {
+                    let bot = &mut self.bots[index];
+                    bot.ping_ms = check.as_ref().map(|check| check.ping.as_millis() as u64);
+                    // A failed check leaves no login to trust, so an older
+                    // name isn't shown against a broken token.
+                    bot.resolved_name = check.as_ref().and_then(|check| check.login.clone());
+                    if let Some(info) = &info {
+                        bot.apply_token_info(info);
+                    }
+                    let missing = bot.missing_scopes.clone();
+                    let can_send = !missing.iter().any(|scope| scope == helix::SEND_SCOPE);
+                    if !can_send {
+                        self.chat_history.push(ChatEntry::info(format!(
+                            "⚠️ {}: this token can't send messages — missing {} scope",
+                            bot.name,
+                            helix::SEND_SCOPE
+                        )));
+                    }
+                    if missing.iter().any(|scope| scope == helix::READ_SCOPE) {
+                        self.chat_history.push(ChatEntry::info(format!(
+                            "⚠️ {}: this token can't read chat — missing {} scope",
+                            bot.name,
+                            helix::READ_SCOPE
+                        )));
+                    }
+                    bot.set_available(check.is_some() && can_send);
+                }
+                self.pending_checks = self.pending_checks.saturating_sub(1);
+                if self.pending_checks == 0 {
+                    self.bots_checked = true;
+                }
+                Task::none()
+            },
+            Message::RemoveDeadBots => {
+                let dead_count = self.bots.iter().filter(|bot| bot.status == BotStatus::Unavailable).count();
+
+                if !self.bots_checked || dead_count == 0 {
+                    return Task::none();
+                }
+
+                let dialog = AsyncMessageDialog::new()
+                    .set_level(MessageLevel::Warning)
+                    .set_title("Remove dead bots")
+                    .set_description(format!(
+                        "Remove {} of {} bots that failed the last check?",
+                        dead_count,
+                        self.bots.len()
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                Task::perform(dialog, |result| {
+                    if result == MessageDialogResult::Yes {
+                        Message::ConfirmRemoveDeadBots
+                    } else {
+                        Message::None
+                    }
+                })
+            },
+            Message::ConfirmRemoveDeadBots => {
+                // Statuses may have changed while the question was open.
+                let dead: HashSet<String> = self.bots
+                    .iter()
+                    .filter(|bot| bot.status == BotStatus::Unavailable)
+                    .map(|bot| bot.token.clone())
+                    .collect();
+                let dead_ids: HashSet<u64> = self.bots
+                    .iter()
+                    .filter(|bot| bot.status == BotStatus::Unavailable)
+                    .map(|bot| bot.id)
+                    .collect();
+                if dead_ids.is_empty() {
+                    return Task::none();
+                }
+
+                // Sends still queued for a removed bot are taken back, and the
+                // running sequence drops its steps. Other state that refers to
+                // a removed bot by id just stops matching.
+                for &id in &dead_ids {
+                    self.cancel_pending_sends(id);
+                }
+                if let Some(run) = self.sequence_run.as_mut() {
+                    let done = run.steps[..run.next].iter().filter(|(id, _)| dead_ids.contains(id)).count();
+                    run.steps.retain(|(id, _)| !dead_ids.contains(id));
+                    run.next -= done;
+                }
+                self.bots.retain(|bot| bot.status != BotStatus::Unavailable);
+                self.chat_history.push(ChatEntry::info(format!("🧹 Removed {} dead bots", dead_ids.len())));
+
+                let Some(path) = self.tokens_path.clone().filter(|path| is_line_config(path)) else {
+                    return Task::none();
+                };
+                let dialog = AsyncMessageDialog::new()
+                    .set_level(MessageLevel::Info)
+                    .set_title("Remove dead bots")
+                    .set_description(format!(
+                        "Also remove them from {}?",
+                        path.display()
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                Task::perform(dialog, move |result| {
+                    if result == MessageDialogResult::Yes {
+                        Message::RemoveDeadTokensFromFile(path.clone(), dead.clone())
+                    } else {
+                        Message::None
+                    }
+                })
+            },
+            Message::RemoveDeadTokensFromFile(path, dead) => {
+                match remove_tokens_from_file(&path, &dead) {
+                    Ok(()) => self.chat_history.push(ChatEntry::info(format!("💾 Updated {}", path.display()))),
+                    Err(e) => self.chat_history.push(ChatEntry::error(format!("Failed to update token file: {}", e))),
                 }
                 Task::none()
             },
-            Message::ToggleBotEnabled(index, enabled) => {
-                if let Some(bot) = self.bots.get_mut(index) {
+            Message::ToggleBotEnabled(id, enabled) => {
+                if let Some(bot) = self.bot_by_id_mut(id) {
                     bot.set_enabled(enabled);
                 }
                 Task::none()
             },
-            Message::SendMessage(index) => {
-                if let Some(bot) = self.bots.get(index) {
-                    if !bot.available || !bot.enable {
-                        return Task::none();
-                    }
+            Message::SetAllBotsEnabled(enabled) => {
+                for index in self.filtered_bot_indices() {
+                    self.bots[index].set_enabled(enabled);
+                }
+                Task::none()
+            },
+            Message::SortBots(key) => {
+                match key {
+                    SortKey::Name => self.bots.sort_by_key(|bot| bot.name.to_lowercase()),
+                    SortKey::Availability => self.bots.sort_by_key(|bot| !bot.is_available()),
+                    SortKey::Enabled => self.bots.sort_by_key(|bot| !bot.enable),
+                    SortKey::MessageCount => self.bots.sort_by_key(|bot| std::cmp::Reverse(bot.chat_history.len())),
+                }
+                self.bot_sort = Some(key);
+                self.round_robin_cursor = 0;
+                Task::none()
+            },
+            Message::InvertBotsEnabled => {
+                for index in self.filtered_bot_indices() {
+                    let enabled = self.bots[index].enable;
+                    self.bots[index].set_enabled(!enabled);
+                }
+                Task::none()
+            },
+            Message::BotChannelUpdated(id, channel) => {
+                if let Some(bot) = self.bot_by_id_mut(id) {
+                    let channel = channel.trim().trim_start_matches('#');
+                    bot.channel = (!channel.is_empty()).then(|| channel.to_string());
+                }
+                Task::none()
+            },
+            Message::ToggleBotModerator(id) => {
+                if let Some(bot) = self.bot_by_id_mut(id) {
+                    bot.moderator = !bot.moderator;
+                }
+                Task::none()
+            },
+            Message::SendMessage(id) => {
+                let Some(index) = self.bot_index(id) else {
+                    return Task::none();
+                };
+                if self.reject_command(&self.message.clone()) {
+                    return Task::none();
+                }
 
-                    let bot_clone = bot.clone();
-                    let channel = self.channel.clone();
-                    let message = self.message.clone();
+                let Some(bot) = self.bots.get(index).filter(|bot| bot.is_available() && bot.enable) else {
+                    return Task::none();
+                };
+                let channel = self.bot_channel(bot);
+                let message = self.message.clone();
+                let action = self.begin_action();
+                let Some(staged) = self.stage_send(index, &channel, action, false, &message) else {
+                    return Task::none();
+                };
 
-                    self.chat_history.push(format!("[{}] {}", bot.name, message));
-                    
-                    if let Some(bot) = self.bots.get_mut(index) {
-                        bot.add_to_history(format!("[{}] {}", bot.name, message));
-                    }
-                    
-                    if self.clear_after_send {
-                        self.message.clear();
-                    }
+                self.clear_sent_message(HashSet::from([staged.bot_id]));
 
-                    Task::perform(
-                        async move {
-                            bot_clone.send_message(&channel, &message).await
-                        },
-                        move |result| {
-                            match result {
-                                Ok(_) => Message::MessageSent(index, Ok(())),
-                                Err(e) => Message::MessageSent(index, Err(e.to_string())),
-                            }
-                        }
-                    )
-                } else {
-                    Task::none()
+                self.dispatch_send(staged, 0)
+            },
+            Message::SendMessageSingle => {
+                match self.take_single_bot() {
+                    Some(index) => Task::done(Message::SendMessage(self.bots[index].id)),
+                    None => Task::none(),
                 }
             },
 
@@ -224,10 +1118,14 @@ impl App {
                     return Task::none();
                 }
 
+                if self.reject_command(&self.message.clone()) {
+                    return Task::none();
+                }
+
                 let available_bots: Vec<usize> = self.bots
                     .iter()
                     .enumerate()
-                    .filter(|(_, bot)| bot.available && bot.enable)
+                    .filter(|(_, bot)| bot.is_available() && bot.enable)
                     .map(|(idx, _)| idx)
                     .collect();
 
@@ -237,116 +1135,96 @@ impl App {
 
                 let mut rng = rand::rng();
                 let bot_index = available_bots[rng.random_range(0..available_bots.len())];
+                let channel = self.bot_channel(&self.bots[bot_index]);
+                let message = self.message.clone();
+                let action = self.begin_action();
+                let Some(staged) = self.stage_send(bot_index, &channel, action, true, &message) else {
+                    return Task::none();
+                };
 
-                if let Some(bot) = self.bots.get(bot_index) {
-                    let bot_clone = bot.clone();
-                    let channel = self.channel.clone();
-                    let message = self.message.clone();
-                    self.chat_history.push(format!("[🎲 {}] {}", bot.name, message));
-                    
-                    if let Some(bot_mut) = self.bots.get_mut(bot_index) {
-                        bot_mut.add_to_history(format!("[🎲 {}] {}", bot_mut.name, message));
-                    }
-                    
-                    if self.clear_after_send {
-                        self.message.clear();
-                    }
+                self.clear_sent_message(HashSet::from([staged.bot_id]));
 
-                    Task::perform(
-                        async move {
-                            bot_clone.send_message(&channel, &message).await
-                        },
-                        move |result| {
-                            match result {
-                                Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                            }
-                        }
-                    )
-                } else {
-                    Task::none()
-                }
+                self.dispatch_send(staged, 0)
             },
             Message::SendMessageAllBots => {
                 if self.message.is_empty() || self.channel.is_empty() {
                     return Task::none();
                 }
+                let quoted = format!("\"{}\"", truncate_display(&self.message, 100).unwrap_or_else(|| self.message.clone()));
+                if let Some(task) = self.confirm_mass_send(MassSend::AllBots, &quoted, self.mass_send_targets()) {
+                    return task;
+                }
 
-                let available_bots: Vec<usize> = self.bots
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, bot)| bot.available && bot.enable)
-                    .map(|(idx, _)| idx)
-                    .collect();
-
-                if available_bots.is_empty() {
+                let action = self.begin_action();
+                let Some((task, bots)) = self.send_with_all_bots(None, action, self.message.clone()) else {
                     return Task::none();
-                }
+                };
 
-                let message = self.message.clone();
-                let mut tasks = Vec::new();
-                let mut rng = rand::rng();
+                self.clear_sent_message(bots);
 
-                for (delay_index, &bot_index) in available_bots.iter().enumerate() {
-                    if let Some(bot) = self.bots.get(bot_index) {
-                        let bot_clone = bot.clone();
-                        let channel = self.channel.clone();
-                        let msg_clone = message.clone();
-                        
-                        self.chat_history.push(format!("[{}] {}", bot.name, message));
-                        
-                        if let Some(bot_mut) = self.bots.get_mut(bot_index) {
-                            bot_mut.add_to_history(format!("[{}] {}", bot_mut.name, message));
-                        }
-                        
-                        if self.simultaneous_mode {
-                            tasks.push(Task::perform(
-                                async move {
-                                    bot_clone.send_message(&channel, &msg_clone).await
-                                },
-                                move |result| {
-                                    match result {
-                                        Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                        Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                    }
-                                }
-                            ));
-                        } else {
-                            let delay = rng.random_range(self.min_bot_delay..=self.max_bot_delay) * delay_index as u64;
-                            tasks.push(Task::perform(
-                                async move {
-                                    if delay > 0 {
-                                        async_std::task::sleep(Duration::from_secs(delay)).await;
-                                    }
-                                    bot_clone.send_message(&channel, &msg_clone).await
-                                },
-                                move |result| {
-                                    match result {
-                                        Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                        Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                    }
-                                }
-                            ));
-                        }
-                    }
+                task
+            },
+            Message::SendDropped(id, send) => {
+                self.roll_back_send(id, send);
+                self.finish_pending(id, send);
+                self.undone_sends.remove(&send);
+                self.settle_cleared_message(id, false);
+                if let Some(bot) = self.bot_by_id_mut(id) {
+                    bot.add_to_history(ChatEntry::info("✖ Delayed send called off"));
                 }
-                
-                if self.clear_after_send {
-                    self.message.clear();
+                Task::none()
+            },
+            Message::MessageSent(id, send, result) => {
+                self.finish_pending(id, send);
+
+                let index = self.bot_index(id);
+                let undone = self.undone_sends.remove(&send);
+                self.last_action.report(send, &result);
+                // Undone before it went out, so it is deleted now instead.
+                let deletes = match &result {
+                    Ok(delivery) if undone => {
+                        let ids: Vec<_> = delivery.message_ids.iter().map(|(channel, message_id)| (id, channel.clone(), message_id.clone())).collect();
+                        self.delete_sent_messages(&ids)
+                    },
+                    _ => Vec::new(),
+                };
+
+                if let Some(bot) = index.and_then(|index| self.bots.get_mut(index)) {
+                    bot.record_result(result.is_ok(), result.as_ref().ok().map(|delivery| delivery.latency));
                 }
 
-                Task::batch(tasks)
-            },
-            Message::MessageSent(index, result) => {
+                // An undone send has no history line left, so it isn't counted.
+                if !undone && let Some(bot) = index.and_then(|index| self.bots.get_mut(index)) {
+                    let stats = self.bot_stats.entry(id).or_default();
+                    match &result {
+                        Ok(_) => stats.sent += 1,
+                        Err(_) => stats.failed += 1,
+                    }
+                    match &result {
+                        Ok(delivery) => {
+                            self.session.record_sent(&bot.name);
+                            if delivery.retries > 0 {
+                                bot.add_to_history(ChatEntry::info(format!("↻ Sent after retrying ({}/{})", delivery.retries, self.max_send_retries)));
+                            }
+                        },
+                        Err(error) => self.session.record_failed(&bot.name, error),
+                    }
+                }
+
+                self.settle_cleared_message(id, result.is_ok());
+
                 if let Err(error) = result {
-                    let error_msg = format!("❌ Error: {}", error);
-                    self.chat_history.push(error_msg.clone());
+                    let entry = ChatEntry::error(error);
                     
-                    if let Some(bot) = self.bots.get_mut(index) {
-                        bot.add_to_history(error_msg);
+                    match index.and_then(|index| self.bots.get_mut(index)) {
+                        Some(bot) => {
+                            bot.add_to_history(entry.clone());
+                            self.chat_history.push(entry.with_bot(bot.id, bot.name.clone()));
+                        },
+                        None => self.chat_history.push(entry),
                     }
                 }
-                Task::none()
+                Task::batch(deletes)
             },
             Message::None => Task::none(),
             Message::ChannelNameUpdated(name) => {
@@ -354,26 +1232,61 @@ impl App {
                 Task::none()
             },
             Message::LoadedMessages(messages) => {
-                self.messages = messages
-                    .lines()
-                    .filter(|line| !line.trim().is_empty())
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>();
-
-                self.messages_editor = text_editor::Content::with_text(&messages);
-
+                self.message_meta.clear();
+                self.set_messages(messages);
+                Task::none()
+            },
+            Message::LoadedMessageEntries(entries) => {
+                let messages = entries.iter().map(|entry| entry.text.clone()).collect();
+                self.message_meta = entries
+                    .into_iter()
+                    .map(|entry| (entry.text.clone(), entry))
+                    .collect();
+                self.set_messages(messages);
+                Task::none()
+            },
+            Message::MessagesLoadFailed(error) => {
+                self.loading_messages = false;
+                self.chat_history.push(ChatEntry::error(format!("failed to load messages: {}", error)));
                 Task::none()
             },
             Message::LoadMessagesPress => {
                 let file = FileDialog::new()
-                    .add_filter("Text Document", &["txt"]);
+                    .add_filter("Messages", &["txt", "csv", "json"])
+                    .add_filter("Text Document", &["txt"])
+                    .add_filter("CSV", &["csv"])
+                    .add_filter("JSON", &["json"]);
     
                 if let Some(path) = file.pick_file() {
+                    self.loading_messages = true;
+                    self.messages_path = Some(path.clone());
                     Task::perform(
                         async move {
-                            match std::fs::read_to_string(path) {
-                                Ok(content) => Message::LoadedMessages(content),
-                                Err(_) => Message::None,
+                            let content = match std::fs::read_to_string(&path) {
+                                Ok(content) => content,
+                                Err(e) => return Message::MessagesLoadFailed(e.to_string()),
+                            };
+
+                            let extension = path
+                                .extension()
+                                .and_then(|ext| ext.to_str())
+                                .map(|ext| ext.to_lowercase());
+
+                            let entries = match extension.as_deref() {
+                                Some("json") => parse_messages_json(&content),
+                                Some("csv") => parse_messages_csv(&content),
+                                _ => return Message::LoadedMessages(
+                                    content
+                                        .lines()
+                                        .filter(|line| !line.trim().is_empty())
+                                        .map(|s| s.to_string())
+                                        .collect()
+                                ),
+                            };
+
+                            match entries {
+                                Ok(entries) => Message::LoadedMessageEntries(entries),
+                                Err(e) => Message::MessagesLoadFailed(e.to_string()),
                             }
                         },
                         |msg| msg
@@ -382,39 +1295,270 @@ impl App {
                     Task::none()
                 }
             },
+            Message::SaveMessagesPress => {
+                let mut file = FileDialog::new()
+                    .add_filter("Messages", &["txt", "csv", "json"])
+                    .add_filter("Text Document", &["txt"])
+                    .add_filter("CSV", &["csv"])
+                    .add_filter("JSON", &["json"]);
+                if let Some(path) = &self.messages_path {
+                    if let Some(dir) = path.parent() {
+                        file = file.set_directory(dir);
+                    }
+                    if let Some(name) = path.file_name() {
+                        file = file.set_file_name(name.to_string_lossy());
+                    }
+                }
+
+                let Some(path) = file.save_file() else {
+                    return Task::none();
+                };
+
+                // The editor only holds the first lines of a very large list.
+                let content = if self.editor_truncated {
+                    self.messages.join("\n")
+                } else {
+                    self.messages_editor.text()
+                };
+
+                // CSV and JSON keep each line's weight, cooldown, list and
+                // enabled flag; a .txt list can only hold the text.
+                let extension = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| ext.to_lowercase());
+                let content = match extension.as_deref() {
+                    Some("json") => write_messages_json(&self.message_entries(&content)),
+                    Some("csv") => write_messages_csv(&self.message_entries(&content)),
+                    _ => Ok(content),
+                };
+                let content = match content {
+                    Ok(content) => content,
+                    Err(e) => {
+                        self.chat_history.push(ChatEntry::error(format!("Failed to save messages: {}", e)));
+                        return Task::none();
+                    },
+                };
+
+                Task::perform(
+                    async move {
+                        std::fs::write(&path, content).map(|_| path).map_err(|e| e.to_string())
+                    },
+                    Message::MessagesSaved
+                )
+            },
+            Message::MessagesSaved(result) => {
+                match result {
+                    Ok(path) => {
+                        self.chat_history.push(ChatEntry::info(format!("💾 Saved messages to {}", path.display())));
+                        self.messages_path = Some(path);
+                    },
+                    Err(e) => self.chat_history.push(ChatEntry::error(format!("Failed to save messages: {}", e))),
+                }
+                Task::none()
+            },
 
             Message::ToggleRandomMessages(enabled) => {
                 self.random_messages_enabled = enabled;
                 if enabled {
                     self.schedule_next_message();
                 } else {
+                    self.cancel_delayed_sends();
                     self.next_message_time = None;
+                    self.live_holding = false;
                 }
                 Task::none()
             },
             Message::MinIntervalUpdated(value) => {
                 if let Ok(val) = value.parse::<u64>() {
                     self.min_interval = val;
+                    self.reschedule_next_message();
                 }
                 Task::none()
             },
             Message::MaxIntervalUpdated(value) => {
                 if let Ok(val) = value.parse::<u64>() {
                     self.max_interval = val;
+                    self.reschedule_next_message();
+                }
+                Task::none()
+            },
+            Message::MaxConcurrentChecksUpdated(value) => {
+                if let Ok(val) = value.parse::<usize>()
+                    && val > 0 {
+                    self.settings.max_concurrent_checks = val;
+                }
+                Task::none()
+            },
+            Message::TimeoutUpdated(field, value) => {
+                if let Ok(val) = value.parse::<u64>()
+                    && val > 0 {
+                    *field.slot(&mut self.settings.timeouts) = val.min(MAX_TIMEOUT_MS);
+                }
+                Task::none()
+            },
+            Message::ResetTimeouts => {
+                self.settings.timeouts = Timeouts::default();
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleAdvanced => {
+                self.show_advanced = !self.show_advanced;
+                Task::none()
+            },
+            Message::OpenLogFile => {
+                if let Err(e) = logging::open_log_file() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to open log file: {}", e)));
+                }
+                Task::none()
+            },
+            Message::BotCooldownUpdated(value) => {
+                if let Ok(val) = value.parse::<u64>() {
+                    self.bot_cooldown = val;
                 }
                 Task::none()
             },
             Message::Tick(now) => {
+                let scheduled = self.run_due_schedules();
                 if self.random_messages_enabled {
                     if let Some(next_time) = self.next_message_time {
                         if now >= next_time {
-                            return Task::done(Message::SendRandomMessage);
+                            return Task::batch([scheduled, self.send_random_if_live(now)]);
                         }
                     }
                 }
+                scheduled
+            },
+            Message::PollMentions => {
+                for bot in &mut self.bots {
+                    for mention in bot.take_mentions() {
+                        let entry = ChatEntry::received(bot.id, bot.name.clone(), format!("{}: {}", mention.sender, mention.text))
+                            .with_channel(mention.channel);
+                        bot.add_to_history(entry.clone());
+                        self.chat_history.push(entry);
+                        self.bot_stats.entry(bot.id).or_default().received += 1;
+                    }
+                }
+                Task::none()
+            },
+            Message::ToggleTokenTester => {
+                self.show_token_tester = !self.show_token_tester;
+                self.tester_token.clear();
+                self.tester_result = None;
+                Task::none()
+            },
+            Message::TesterTokenUpdated(token) => {
+                self.tester_token = token;
+                self.tester_result = None;
+                Task::none()
+            },
+            Message::TestToken => {
+                let token = self.tester_token.trim().trim_start_matches("oauth:").to_string();
+                if token.is_empty() || self.tester_running {
+                    return Task::none();
+                }
+
+                self.tester_running = true;
+                self.tester_result = None;
+                let timeouts = self.settings.timeouts;
+                let tls = self.settings.use_tls;
+                Task::perform(
+                    async move {
+                        let info = helix::validate_token(&token).await?;
+                        let irc_ok = test_irc_connection(&info.login, &token, timeouts, tls).await?.is_some();
+                        anyhow::Ok(TokenTestReport { token, info, irc_ok })
+                    },
+                    |result| Message::TokenTested(result.map_err(|e| e.to_string()))
+                )
+            },
+            Message::TokenTested(result) => {
+                self.tester_running = false;
+                self.tester_result = Some(result);
+                Task::none()
+            },
+            Message::AddTestedBot => {
+                if let Some(Ok(report)) = &self.tester_result
+                    && report.irc_ok {
+                    if self.bots.iter().any(|bot| bot.token == report.token) {
+                        self.chat_history.push(ChatEntry::info(format!("⚠️ {} is already in the bot list", report.info.login)));
+                    } else {
+                        let mut bot = Bot::new(report.info.login.clone(), report.token.clone());
+                        bot.apply_token_info(&report.info);
+                        bot.set_available(!bot.missing_scopes.iter().any(|scope| scope == helix::SEND_SCOPE));
+                        self.bots.push(bot);
+                        self.chat_history.push(ChatEntry::info(format!("✅ Added {} as a bot", report.info.login)));
+                    }
+                    self.show_token_tester = false;
+                    self.tester_token.clear();
+                    self.tester_result = None;
+                }
+                Task::none()
+            },
+            Message::PresetNameUpdated(name) => {
+                self.preset_name = name;
+                Task::none()
+            },
+            Message::SavePreset(name) => {
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return Task::none();
+                }
+
+                self.presets.insert(name.clone(), self.current_preset());
+                match presets::save_presets(&self.presets) {
+                    Ok(()) => self.chat_history.push(ChatEntry::info(format!("💾 Saved preset \"{}\"", name))),
+                    Err(e) => self.chat_history.push(ChatEntry::error(format!("Failed to save presets: {}", e))),
+                }
+                self.active_preset = Some(name);
+                self.preset_name.clear();
+                Task::none()
+            },
+            Message::ApplyPreset(name) => {
+                if let Some(preset) = self.presets.get(&name).cloned() {
+                    self.cancel_delayed_sends();
+                    self.apply_preset(preset);
+                    self.active_preset = Some(name);
+                }
+                Task::none()
+            },
+            Message::ToggleRequireLive(enabled) => {
+                for channel in parse_channels(&self.channel) {
+                    let channel = channel.to_lowercase();
+                    if enabled {
+                        self.require_live_channels.insert(channel);
+                    } else {
+                        self.require_live_channels.remove(&channel);
+                    }
+                }
+                if !enabled {
+                    self.live_holding = false;
+                }
+                Task::none()
+            },
+            Message::LiveStatusChecked(channel, result) => {
+                self.live_check_pending.remove(&channel);
+                // A failed check leaves the status unknown, which doesn't hold
+                // sends; it is tried again once the entry goes stale.
+                let live = match result {
+                    Ok(live) => Some(live),
+                    Err(error) => {
+                        self.chat_history.push(ChatEntry::error(format!("Live check failed: {}", error)));
+                        None
+                    }
+                };
+                self.live_status.insert(channel, (live, Instant::now()));
                 Task::none()
             },
             Message::SendRandomMessageNow => {
+                if self.all_bots_mode || self.multiple_bots_mode {
+                    let (bots, channels) = self.mass_send_targets();
+                    let bots = if self.all_bots_mode { bots } else { bots.min(self.multiple_bots_count) };
+                    if let Some(task) = self.confirm_mass_send(MassSend::RandomNow, "a random message", (bots, channels)) {
+                        return task;
+                    }
+                }
                 self.next_message_time = None;
                 Task::done(Message::SendRandomMessage)
             },
@@ -423,10 +1567,13 @@ impl App {
                     return Task::none();
                 }
 
+                // Bots still cooling down sit this one out; if that's all of
+                // them the tick is skipped and retried on the next one.
+                let cooldown = Duration::from_secs(self.bot_cooldown);
                 let available_bots: Vec<usize> = self.bots
                     .iter()
                     .enumerate()
-                    .filter(|(_, bot)| bot.available && bot.enable)
+                    .filter(|(_, bot)| bot.is_available() && bot.enable && !bot.is_cooling_down(cooldown))
                     .map(|(idx, _)| idx)
                     .collect();
 
@@ -434,6 +1581,17 @@ impl App {
                     return Task::none();
                 }
 
+                let ready_messages = self.ready_messages();
+                if ready_messages.is_empty() {
+                    return Task::none();
+                }
+
+                if self.burst_limiter.is_full() {
+                    self.burst_holding = true;
+                    return Task::none();
+                }
+
+                let action = self.begin_action();
                 let mut rng = rand::rng();
 
                 if self.multiple_bots_mode {
@@ -441,119 +1599,69 @@ impl App {
                     let bots_to_use = self.multiple_bots_count.min(available_bots.len());
                     
                     let mut shuffled_bots = available_bots.clone();
-                    use rand::seq::SliceRandom;
                     shuffled_bots.shuffle(&mut rng);
                     
+                    let pool: Vec<String> = ready_messages
+                        .iter()
+                        .map(|(text, _)| text.clone())
+                        .collect();
+                    let mut used_messages = Vec::new();
+                    
                     for i in 0..bots_to_use {
                         let bot_index = shuffled_bots[i];
                         
-                        if let Some(bot) = self.bots.get(bot_index) {
-                            let message = if self.messages.len() > i {
-                                let mut shuffled_messages = self.messages.clone();
-                                shuffled_messages.shuffle(&mut rng);
-                                shuffled_messages[i % shuffled_messages.len()].clone()
-                            } else {
-                                self.messages[rng.random_range(0..self.messages.len())].clone()
-                            };
-                            
-                            let bot_clone = bot.clone();
-                            let channel = self.channel.clone();
-                            let msg_clone = message.clone();
-                            
-                            let history_msg = format!("[🎲 {}] {}", bot.name, message);
-                            self.chat_history.push(history_msg.clone());
-                            
-                            if let Some(bot_mut) = self.bots.get_mut(bot_index) {
-                                bot_mut.add_to_history(history_msg);
-                            }
-                            
-                            if self.simultaneous_mode {
-                                tasks.push(Task::perform(
-                                    async move {
-                                        bot_clone.send_message(&channel, &msg_clone).await
-                                    },
-                                    move |result| {
-                                        match result {
-                                            Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                            Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                        }
-                                    }
-                                ));
-                            } else {
-                                let delay = rng.random_range(self.min_bot_delay..=self.max_bot_delay) * i as u64;
-                                tasks.push(Task::perform(
-                                    async move {
-                                        if delay > 0 {
-                                            async_std::task::sleep(Duration::from_secs(delay)).await;
-                                        }
-                                        bot_clone.send_message(&channel, &msg_clone).await
-                                    },
-                                    move |result| {
-                                        match result {
-                                            Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                            Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                        }
-                                    }
-                                ));
-                            }
+                        let message = if self.no_repeats {
+                            self.draw_from_bag(&ready_messages, &mut rng)
+                        } else if pool.len() > i {
+                            let mut shuffled_messages = pool.clone();
+                            shuffled_messages.shuffle(&mut rng);
+                            shuffled_messages[i % shuffled_messages.len()].clone()
+                        } else {
+                            pool[rng.random_range(0..pool.len())].clone()
+                        };
+                        used_messages.push(message.clone());
+
+                        let channel = self.bot_channel(&self.bots[bot_index]);
+                        let delay = if self.simultaneous_mode {
+                            0
+                        } else {
+                            rng.random_range(self.min_bot_delay..=self.max_bot_delay) * i as u64
+                        };
+                        match self.stage_send(bot_index, &channel, action, true, &message) {
+                            Some(staged) => tasks.push(self.dispatch_send(staged, delay)),
+                            None if self.burst_holding => break,
+                            None => {}
                         }
                     }
                     
+                    for message in &used_messages {
+                        self.mark_message_used(message);
+                    }
+                    
                     self.last_message_time = Some(Instant::now());
                     
                     if self.random_messages_enabled {
                         self.schedule_next_message();
                     }
                     
-                    return Task::batch(tasks);
+                    Task::batch(tasks)
                 }
                 else if self.all_bots_mode {
-                    let message = self.messages[rng.random_range(0..self.messages.len())].clone();
+                    let message = self.pick_random_message(&ready_messages, &mut rng);
                     
                     let mut tasks = Vec::new();
                     
                     for (delay_index, &bot_index) in available_bots.iter().enumerate() {
-                        if let Some(bot) = self.bots.get(bot_index) {
-                            let bot_clone = bot.clone();
-                            let channel = self.channel.clone();
-                            let msg_clone = message.clone();
-                            
-                            let history_msg = format!("[🎲 {}] {}", bot.name, message);
-                            self.chat_history.push(history_msg.clone());
-                            
-                            if let Some(bot_mut) = self.bots.get_mut(bot_index) {
-                                bot_mut.add_to_history(history_msg);
-                            }
-                            
-                            if self.simultaneous_mode {
-                                tasks.push(Task::perform(
-                                    async move {
-                                        bot_clone.send_message(&channel, &msg_clone).await
-                                    },
-                                    move |result| {
-                                        match result {
-                                            Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                            Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                        }
-                                    }
-                                ));
-                            } else {
-                                let delay = rng.random_range(self.min_bot_delay..=self.max_bot_delay) * delay_index as u64;
-                                tasks.push(Task::perform(
-                                    async move {
-                                        if delay > 0 {
-                                            async_std::task::sleep(Duration::from_secs(delay)).await;
-                                        }
-                                        bot_clone.send_message(&channel, &msg_clone).await
-                                    },
-                                    move |result| {
-                                        match result {
-                                            Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                            Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                        }
-                                    }
-                                ));
-                            }
+                        let channel = self.bot_channel(&self.bots[bot_index]);
+                        let delay = if self.simultaneous_mode {
+                            0
+                        } else {
+                            rng.random_range(self.min_bot_delay..=self.max_bot_delay) * delay_index as u64
+                        };
+                        match self.stage_send(bot_index, &channel, action, true, &message) {
+                            Some(staged) => tasks.push(self.dispatch_send(staged, delay)),
+                            None if self.burst_holding => break,
+                            None => {}
                         }
                     }
                     
@@ -563,47 +1671,32 @@ impl App {
                         self.schedule_next_message();
                     }
                     
-                    return Task::batch(tasks);
+                    Task::batch(tasks)
                 } 
                 else {
-                    let message = self.messages[rng.random_range(0..self.messages.len())].clone();
+                    let message = self.pick_random_message(&ready_messages, &mut rng);
                     let bot_index = available_bots[rng.random_range(0..available_bots.len())];
-                    
-                    if let Some(bot) = self.bots.get(bot_index) {
-                        let bot_clone = bot.clone();
-                        let channel = self.channel.clone();
-                        let msg_clone = message.clone();
+                    let channel = self.bot_channel(&self.bots[bot_index]);
 
-                        let history_msg = format!("[🎲 {}] {}", bot.name, message);
-                        self.chat_history.push(history_msg.clone());
-                        
-                        if let Some(bot_mut) = self.bots.get_mut(bot_index) {
-                            bot_mut.add_to_history(history_msg);
-                        }
-                        
-                        self.last_message_time = Some(Instant::now());
-                        
-                        if self.random_messages_enabled {
+                    let Some(staged) = self.stage_send(bot_index, &channel, action, true, &message) else {
+                        // A held send is tried again on the next tick.
+                        if !self.burst_holding && self.random_messages_enabled {
                             self.schedule_next_message();
                         }
+                        return Task::none();
+                    };
 
-                        return Task::perform(
-                            async move {
-                                bot_clone.send_message(&channel, &msg_clone).await
-                            },
-                            move |result| {
-                                match result {
-                                    Ok(_) => Message::MessageSent(bot_index, Ok(())),
-                                    Err(e) => Message::MessageSent(bot_index, Err(e.to_string())),
-                                }
-                            }
-                        );
+                    self.last_message_time = Some(Instant::now());
+                    
+                    if self.random_messages_enabled {
+                        self.schedule_next_message();
                     }
+
+                    self.dispatch_send(staged, 0)
                 }
-                
-                Task::none()
             },
             Message::ToggleAllBotsMode(enabled) => {
+                self.cancel_delayed_sends();
                 self.all_bots_mode = enabled;
                 if enabled && self.multiple_bots_mode {
                     self.multiple_bots_mode = false;
@@ -611,6 +1704,7 @@ impl App {
                 Task::none()
             },
             Message::ToggleSimultaneousMode(enabled) => {
+                self.cancel_delayed_sends();
                 self.simultaneous_mode = enabled;
                 Task::none()
             },
@@ -627,6 +1721,7 @@ impl App {
                 Task::none()
             },
             Message::ToggleMultipleBotsMode(enabled) => {
+                self.cancel_delayed_sends();
                 self.multiple_bots_mode = enabled;
                 if enabled && self.all_bots_mode {
                     self.all_bots_mode = false;
@@ -645,154 +1740,2114 @@ impl App {
                 self.clear_after_send = enabled;
                 Task::none()
             },
+            Message::ToggleAutoSplit(enabled) => {
+                self.auto_split = enabled;
+                Task::none()
+            },
+            Message::ToggleRejectUnknownCommands(enabled) => {
+                self.reject_unknown_commands = enabled;
+                Task::none()
+            },
+            Message::ToggleNumberSplitParts(enabled) => {
+                self.number_split_parts = enabled;
+                Task::none()
+            },
             Message::MessagesEditorAction(action) => {
+                // A capped editor only holds part of the list, so rebuilding
+                // `messages` from it would drop the rest.
+                if self.editor_truncated && action.is_edit() {
+                    return Task::none();
+                }
+
+                let is_edit = action.is_edit();
+                let is_click = matches!(action, text_editor::Action::Click(_));
                 self.messages_editor.perform(action);
-                
-                let editor_text = self.messages_editor.text();
-                self.messages = editor_text
-                    .lines()
-                    .filter(|line| !line.trim().is_empty())
-                    .map(|s| s.to_string())
-                    .collect();
-                
+
+                if is_edit {
+                    let editor_text = self.messages_editor.text();
+                    self.messages = editor_text
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(|s| s.to_string())
+                        .collect();
+                    self.long_lines = long_line_numbers(&self.messages);
+                    self.message_bag.clear();
+                    self.selected_editor_line = None;
+                } else if is_click && self.editor_picks_message {
+                    let line = self.messages_editor.cursor().position.line;
+                    if let Some(text) = self.editor_line(line) {
+                        self.message = text;
+                        self.cleared_message = None;
+                        self.selected_editor_line = Some(line);
+                    }
+                }
+
+                Task::none()
+            },
+            Message::ToggleEditorLinePicking(enabled) => {
+                self.editor_picks_message = enabled;
+                if !enabled {
+                    self.selected_editor_line = None;
+                }
+                Task::none()
+            },
+            Message::SaveMessageAsDefault => {
+                self.settings.default_message = self.message.clone();
+                match self.settings.save() {
+                    Ok(()) if self.message.is_empty() => self.chat_history.push(ChatEntry::info("📌 Default message cleared".to_string())),
+                    Ok(()) => self.chat_history.push(ChatEntry::info("📌 Saved as the default message".to_string())),
+                    Err(e) => self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e))),
+                }
                 Task::none()
             },
-            Message::ToggleBotChatView(index) => {
-                self.viewing_bot_chat = Some(index);
+            Message::SendSelectedLine => {
+                match self.selected_editor_line.and_then(|line| self.editor_line(line)) {
+                    Some(text) => {
+                        self.message = text;
+                        Task::done(Message::SendMessageSingle)
+                    },
+                    None => Task::none(),
+                }
+            },
+            Message::ToggleBotChatView(id) => {
+                self.viewing_bot_chat = Some(id);
                 self.bot_message_input.clear();
                 Task::none()
             },
             Message::CloseBotChatView => {
-                self.viewing_bot_chat = None;
+                let viewed = self.viewing_bot_chat.take();
+                self.revealed_token = None;
                 self.bot_message_input.clear();
+
+                // The view closes right away; the pending sends are only
+                // cancelled if the answer comes back yes.
+                let Some(id) = viewed.filter(|&id| self.pending_count(id) > 0) else {
+                    return Task::none();
+                };
+                let dialog = AsyncMessageDialog::new()
+                    .set_level(MessageLevel::Info)
+                    .set_title("Pending sends")
+                    .set_description(format!(
+                        "This bot still has {} pending sends. Cancel them?",
+                        self.pending_count(id)
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                Task::perform(dialog, move |result| {
+                    if result == MessageDialogResult::Yes {
+                        Message::CancelPendingSends(id)
+                    } else {
+                        Message::None
+                    }
+                })
+            },
+            Message::SendToTestChannel(id) => {
+                let Some(index) = self.bot_index(id) else {
+                    return Task::none();
+                };
+                let bot = &self.bots[index];
+                if self.bot_message_input.is_empty() {
+                    return Task::none();
+                }
+
+                let channel = match self.settings.test_channel.trim() {
+                    "" => bot.login.clone(),
+                    channel => channel.trim_start_matches('#').to_lowercase(),
+                };
+
+                let action = self.begin_action();
+                match self.send_from_bot(index, channel.clone(), action, self.bot_message_input.clone()) {
+                    Some(task) => {
+                        self.chat_history.push(ChatEntry::info(format!("🧪 Test send to #{}", channel)));
+                        self.bot_message_input.clear();
+                        task
+                    },
+                    None => Task::none(),
+                }
+            },
+            Message::WhisperTargetUpdated(target) => {
+                self.whisper_target = target;
+                Task::none()
+            },
+            Message::SendWhisper(id) => {
+                let target = self.whisper_target.trim().trim_start_matches('@').to_string();
+                if target.is_empty() || self.bot_message_input.is_empty() {
+                    return Task::none();
+                }
+                let Some(index) = self.bot_index(id) else {
+                    return Task::none();
+                };
+                let bot = &mut self.bots[index];
+                // Whispers go over Helix, not chat, so they only count against
+                // the bot's whisper limits.
+                if let Err(wait) = bot.acquire_whisper() {
+                    bot.add_to_history(ChatEntry::info(format!(
+                        "⏳ Whisper limit reached, try again in {:.1}s",
+                        wait.as_secs_f32()
+                    )));
+                    return Task::none();
+                }
+
+                let message = std::mem::take(&mut self.bot_message_input);
+                let entry = ChatEntry::sent(bot.id, bot.name.clone(), format!("✉️ @{}: {}", target, message));
+                bot.record_sent(entry.clone());
+                self.chat_history.push(entry);
+
+                let credentials = bot.credentials(self.settings.timeouts, self.settings.use_tls);
+                Task::perform(
+                    async move {
+                        let result = credentials.send_whisper(&target, &message).await;
+                        (message, result)
+                    },
+                    move |(message, result)| Message::WhisperSent(id, message, result.map_err(|e| e.to_string()))
+                )
+            },
+            Message::WhisperSent(id, message, result) => {
+                if let Some(index) = self.bot_index(id) {
+                    let bot = &mut self.bots[index];
+                    bot.record_result(result.is_ok(), None);
+                    if let Err(error) = result {
+                        let entry = ChatEntry::error(error);
+                        bot.add_to_history(entry.clone());
+                        self.chat_history.push(entry.with_bot(bot.id, bot.name.clone()));
+
+                        // Give the text back unless something new was typed.
+                        if self.viewing_bot_chat == Some(id) && self.bot_message_input.is_empty() {
+                            self.bot_message_input = message;
+                        }
+                    }
+                }
+                Task::none()
+            },
+            Message::TestChannelUpdated(channel) => {
+                self.settings.test_channel = channel;
+                Task::none()
+            },
+            Message::SaveSettings => {
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::CancelPendingSends(id) => {
+                self.cancel_pending_sends(id);
                 Task::none()
             },
             Message::BotMessageUpdated(msg) => {
                 self.bot_message_input = msg;
                 Task::none()
             },
-            Message::SendBotMessage(index) => {
-                if self.bot_message_input.is_empty() || self.channel.is_empty() {
+            Message::SendBotMessage(id) => {
+                let Some(index) = self.bot_index(id) else {
+                    return Task::none();
+                };
+                let channel = self.bot_channel(&self.bots[index]);
+                if self.bot_message_input.is_empty() || channel.is_empty() {
                     return Task::none();
                 }
-                
-                if let Some(bot) = self.bots.get(index) {
-                    if !bot.available || !bot.enable {
-                        return Task::none();
-                    }
 
-                    let bot_clone = bot.clone();
-                    let channel = self.channel.clone();
-                    let message = self.bot_message_input.clone();
+                let action = self.begin_action();
+                match self.send_from_bot(index, channel, action, self.bot_message_input.clone()) {
+                    Some(task) => {
+                        self.bot_message_input.clear();
+                        task
+                    },
+                    None => Task::none(),
+                }
+            },
+            Message::ExportHistoryPress => {
+                self.export_history_task(self.chat_history.to_vec(), "chat_history")
+            },
+            Message::ExportBotHistory(id) => {
+                match self.bot_by_id(id) {
+                    Some(bot) => self.export_history_task(bot.chat_history.to_vec(), &format!("{}_history", bot.name)),
+                    None => Task::none(),
+                }
+            },
+            Message::HistoryExported(result) => {
+                match result {
+                    Ok(path) => self.chat_history.push(ChatEntry::info(format!("💾 Exported history to {}", path.display()))),
+                    Err(e) => self.chat_history.push(ChatEntry::error(format!("Failed to export history: {}", e))),
+                }
+                Task::none()
+            },
+            Message::ClearBotHistory(id) => {
+                if let Some(bot) = self.bot_by_id_mut(id) {
+                    bot.clear_history();
+                }
+                Task::none()
+            },
+            Message::ClearGlobalHistory => {
+                self.chat_history.clear();
+                Task::none()
+            },
+
+            Message::ClearAllHistory => {
+                self.chat_history.clear();
+                for bot in &mut self.bots {
+                    bot.clear_history();
+                }
+                Task::none()
+            },
+
+            Message::SearchQueryUpdated(query) => {
+                self.search_query = query;
+                Task::none()
+            },
+
+            Message::ChatSearchUpdated(query) => {
+                self.chat_search_query = query;
+                Task::none()
+            },
+
+            Message::ChatScrolled(viewport) => {
+                self.chat_viewport = (viewport.absolute_offset().y, viewport.bounds().height);
+                Task::none()
+            },
+
+            Message::MessageClicked(entry_id) => {
+                if let Some(bot_id) = self.chat_history.find(entry_id).and_then(|entry| entry.bot_id)
+                    && self.bot_by_id(bot_id).is_some() {
+                    self.viewing_bot_chat = Some(bot_id);
+                    self.bot_message_input.clear();
+                }
+                Task::none()
+            },
+
+            Message::CopyChatEntry(entry_id) => {
+                match self.chat_history.find(entry_id) {
+                    Some(entry) => iced::clipboard::write(entry.to_string()),
+                    None => Task::none(),
+                }
+            },
+            Message::DeleteHistoryEntry(entry_id) => {
+                let ids = HashSet::from([entry_id]);
+                self.chat_history.remove_ids(&ids);
+                for bot in &mut self.bots {
+                    bot.chat_history.remove_ids(&ids);
+                }
+                self.last_action.entries.remove(&entry_id);
+                Task::none()
+            },
+
+            Message::HotkeyPressed(hotkey) => {
+                if let Some(action) = self.rebinding_hotkey.take() {
+                    if hotkey != Hotkey::new("Escape") {
+                        self.settings.hotkeys.set(action, Some(hotkey));
+                        if let Err(e) = self.settings.save() {
+                            self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                        }
+                    }
+                    return Task::none();
+                }
+
+                match self.settings.hotkeys.action_for(&hotkey) {
+                    Some(HotkeyAction::RandomNow) => Task::done(Message::SendRandomMessageNow),
+                    Some(HotkeyAction::AllBots) => Task::done(Message::SendMessageAllBots),
+                    Some(HotkeyAction::Single) => {
+                        if self.message.is_empty() || self.channel.is_empty() {
+                            return Task::none();
+                        }
+                        Task::done(Message::SendMessageSingle)
+                    },
+                    None => Task::none(),
+                }
+            },
+            Message::ToggleNoRepeats(enabled) => {
+                self.no_repeats = enabled;
+                Task::none()
+            },
+            Message::LoadBlocklistPress => {
+                let file = FileDialog::new()
+                    .add_filter("Text Document", &["txt"]);
+
+                match file.pick_file() {
+                    Some(path) => Task::perform(
+                        async move { std::fs::read_to_string(&path) },
+                        |result| match result {
+                            Ok(content) => Message::LoadedBlocklist(content),
+                            Err(_) => Message::None,
+                        }
+                    ),
+                    None => Task::none(),
+                }
+            },
+            Message::LoadedBlocklist(content) => {
+                self.settings.blocklist = parse_blocklist(&content);
+                self.chat_history.push(ChatEntry::info(format!("🚫 Loaded {} blocked terms", self.settings.blocklist.len())));
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleMaskBlocked(enabled) => {
+                self.settings.mask_blocked = enabled;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleConfirmMassSends(enabled) => {
+                self.settings.confirm_mass_sends = enabled;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ConfirmMassSend(kind) => {
+                self.mass_send_confirmed = Some(kind);
+                Task::done(kind.message())
+            },
+            Message::CancelMassSend => {
+                self.chat_history.push(ChatEntry::info("✖ Mass send called off"));
+                Task::none()
+            },
+            Message::UndoLastSend => {
+                // Messages from moderator bots are deleted from Twitch chat;
+                // the rest only leave the local history.
+                let action = std::mem::take(&mut self.last_action);
+                let removed = self.chat_history.remove_ids(&action.entries);
+                for bot in &mut self.bots {
+                    bot.chat_history.remove_ids(&action.entries);
+                }
+                let deletes = self.delete_sent_messages(&action.message_ids);
+
+                // Sends that already reported back have their counts taken
+                // back now; the rest are skipped when they report.
+                for (send, (bot_id, outcome)) in action.results {
+                    let Some(outcome) = outcome else {
+                        self.undone_sends.insert(send);
+                        continue;
+                    };
+                    let name = self.bot_by_id(bot_id).map(|bot| bot.name.clone()).unwrap_or_default();
+                    let stats = self.bot_stats.entry(bot_id).or_default();
+                    match outcome {
+                        Ok(()) => {
+                            stats.sent = stats.sent.saturating_sub(1);
+                            self.session.unrecord_sent(&name);
+                        },
+                        Err(error) => {
+                            stats.failed = stats.failed.saturating_sub(1);
+                            self.session.unrecord_failed(&name, &error);
+                        },
+                    }
+                }
+                if removed > 0 {
+                    let twitch = if deletes.is_empty() {
+                        "the messages stay in Twitch chat".to_string()
+                    } else {
+                        format!("deleting {} of its messages from Twitch chat", deletes.len())
+                    };
+                    self.chat_history.push(ChatEntry::info(format!(
+                        "↩ Removed {} history lines of the last send; {}",
+                        removed,
+                        twitch
+                    )));
+                }
+                Task::batch(deletes)
+            },
+            Message::UndoDeleted(id, result) => {
+                if let Err(error) = result {
+                    let entry = ChatEntry::error(error);
+                    match self.bot_index(id).map(|index| &mut self.bots[index]) {
+                        Some(bot) => {
+                            bot.add_to_history(entry.clone());
+                            self.chat_history.push(entry.with_bot(bot.id, bot.name.clone()));
+                        },
+                        None => self.chat_history.push(entry),
+                    }
+                }
+                Task::none()
+            },
+            Message::ToggleStats => {
+                self.show_stats = !self.show_stats;
+                Task::none()
+            },
+            Message::SortStats(sort) => {
+                self.stats_sort = sort;
+                Task::none()
+            },
+            Message::ResetStats => {
+                self.bot_stats.clear();
+                Task::none()
+            },
+            Message::ToggleChannelMatrix => {
+                self.show_channel_matrix = !self.show_channel_matrix;
+                Task::none()
+            },
+            Message::ChannelMatrixAction(action) => {
+                self.channel_matrix_editor.perform(action);
+                Task::none()
+            },
+            Message::ToggleChannelMatrixAllBots(enabled) => {
+                self.channel_matrix_all_bots = enabled;
+                Task::none()
+            },
+            Message::SendChannelMatrix => {
+                let matrix = match self.parse_channel_matrix() {
+                    Ok(matrix) => matrix,
+                    Err(error) => {
+                        self.chat_history.push(ChatEntry::error(error));
+                        return Task::none();
+                    }
+                };
+
+                let bots = if self.channel_matrix_all_bots { self.mass_send_targets().0 } else { matrix.len() };
+                if let Some(task) = self.confirm_mass_send(MassSend::ChannelMatrix, "the multi-channel messages", (bots, matrix.len())) {
+                    return task;
+                }
+
+                let action = self.begin_action();
+                let mut tasks = Vec::new();
+                for (channel, message) in matrix {
+                    let task = if self.channel_matrix_all_bots {
+                        self.send_with_all_bots(Some(channel.clone()), action, message).map(|(task, _)| task)
+                    } else {
+                        self.take_single_bot()
+                            .and_then(|index| self.send_from_bot(index, channel.clone(), action, message))
+                    };
+
+                    match task {
+                        Some(task) => tasks.push(task),
+                        None => self.chat_history.push(ChatEntry::info(format!("⚠️ Nothing sent to #{}", channel))),
+                    }
+                }
+
+                Task::batch(tasks)
+            },
+            Message::ToggleSchedule => {
+                self.show_schedule = !self.show_schedule;
+                Task::none()
+            },
+            Message::ScheduleTimeUpdated(value) => {
+                self.schedule_time = value;
+                Task::none()
+            },
+            Message::ScheduleTextUpdated(value) => {
+                self.schedule_text = value;
+                Task::none()
+            },
+            Message::ScheduleBotSelected(choice) => {
+                self.schedule_bot = choice.id;
+                Task::none()
+            },
+            Message::ScheduleRepeatUpdated(value) => {
+                self.schedule_repeat = value;
+                Task::none()
+            },
+            Message::AddSchedule => {
+                let Some(at) = next_time_at(&self.schedule_time) else {
+                    self.chat_history.push(ChatEntry::error(format!(
+                        "Schedule time \"{}\" should look like 14:30",
+                        self.schedule_time.trim()
+                    )));
+                    return Task::none();
+                };
+                let repeat = match self.schedule_repeat.trim() {
+                    "" => None,
+                    minutes => match minutes.parse::<i64>() {
+                        Ok(minutes) if minutes > 0 => Some(chrono::Duration::minutes(minutes)),
+                        _ => {
+                            self.chat_history.push(ChatEntry::error("Repeat should be a number of minutes"));
+                            return Task::none();
+                        }
+                    },
+                };
+
+                self.schedules.push(ScheduledMessage {
+                    at,
+                    text: self.schedule_text.trim().to_string(),
+                    bot: self.schedule_bot,
+                    repeat,
+                });
+                self.schedules.sort_by_key(|schedule| schedule.at);
+                self.schedule_text.clear();
+                Task::none()
+            },
+            Message::RemoveSchedule(index) => {
+                if index < self.schedules.len() {
+                    self.schedules.remove(index);
+                }
+                Task::none()
+            },
+            Message::ToggleSequenceEditor => {
+                self.show_sequence_editor = !self.show_sequence_editor;
+                Task::none()
+            },
+            Message::SequenceEditorAction(action) => {
+                if self.sequence_run.is_none() || !action.is_edit() {
+                    self.sequence_editor.perform(action);
+                }
+                Task::none()
+            },
+            Message::SequenceGapUpdated(value) => {
+                if let Ok(val) = value.parse::<u64>() {
+                    self.sequence_gap = val;
+                }
+                Task::none()
+            },
+            Message::StartSequence => {
+                if self.sequence_run.is_some() || self.channel.is_empty() {
+                    return Task::none();
+                }
+
+                let steps = match self.parse_sequence() {
+                    Ok(steps) if !steps.is_empty() => steps,
+                    Ok(_) => return Task::none(),
+                    Err(error) => {
+                        self.chat_history.push(ChatEntry::error(error));
+                        return Task::none();
+                    }
+                };
+
+                self.sequence_generation += 1;
+                let action = self.begin_action();
+                self.sequence_run = Some(SequenceRun {
+                    generation: self.sequence_generation,
+                    action,
+                    steps,
+                    next: 0,
+                });
+                Task::done(Message::SequenceStep(self.sequence_generation))
+            },
+            Message::StopSequence => {
+                if let Some(run) = self.sequence_run.take() {
+                    self.chat_history.push(ChatEntry::info(format!(
+                        "⏹️ Sequence stopped at step {}/{}",
+                        run.next,
+                        run.steps.len()
+                    )));
+                }
+                Task::none()
+            },
+            Message::SequenceStep(generation) => {
+                let Some(run) = self.sequence_run.as_ref().filter(|run| run.generation == generation) else {
+                    return Task::none();
+                };
+
+                let action = run.action;
+                let Some((id, message)) = run.steps.get(run.next).cloned() else {
+                    let total = run.steps.len();
+                    self.sequence_run = None;
+                    self.chat_history.push(ChatEntry::info(format!("✅ Sequence finished ({} steps)", total)));
+                    return Task::none();
+                };
+
+                let gap = Duration::from_secs(self.sequence_gap);
+                let next_step = move || Task::perform(
+                    async move { async_std::task::sleep(gap).await },
+                    move |_| Message::SequenceStep(generation)
+                );
+
+                if self.burst_limiter.is_full() {
+                    // Wait for the global limit instead of skipping the step.
+                    return Task::perform(
+                        async { async_std::task::sleep(Duration::from_secs(1)).await },
+                        move |_| Message::SequenceStep(generation)
+                    );
+                }
+
+                if let Some(run) = self.sequence_run.as_mut() {
+                    run.next += 1;
+                }
+
+                let Some(index) = self.bot_index(id) else {
+                    self.chat_history.push(ChatEntry::info("⚠️ Sequence: bot was removed, skipping"));
+                    return next_step();
+                };
+                let channel = self.bot_channel(&self.bots[index]);
+                match self.send_from_bot(index, channel, action, message) {
+                    Some(task) => task.chain(next_step()),
+                    None => {
+                        let name = self.bots[index].name.clone();
+                        self.chat_history.push(ChatEntry::info(format!("⚠️ Sequence: {} is unavailable, skipping", name)));
+                        next_step()
+                    }
+                }
+            },
+            Message::ToggleHotkeySettings => {
+                self.show_hotkey_settings = !self.show_hotkey_settings;
+                self.rebinding_hotkey = None;
+                Task::none()
+            },
+            Message::StartRebindHotkey(action) => {
+                self.rebinding_hotkey = Some(action);
+                Task::none()
+            },
+            Message::ClearHotkey(action) => {
+                self.settings.hotkeys.set(action, None);
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+
+            Message::SingleSendPolicyChanged(policy) => {
+                self.single_send_policy = policy;
+                Task::none()
+            },
+            Message::RevealToken(id) => {
+                let Some(bot) = self.bot_by_id(id) else {
+                    return Task::none();
+                };
+                let dialog = AsyncMessageDialog::new()
+                    .set_level(MessageLevel::Warning)
+                    .set_title("Reveal token")
+                    .set_description(format!(
+                        "Show the full OAuth token for {}? Anyone who can see your screen will be able to use it.",
+                        bot.name
+                    ))
+                    .set_buttons(MessageButtons::YesNo)
+                    .show();
+                Task::perform(dialog, move |result| {
+                    if result == MessageDialogResult::Yes {
+                        Message::ConfirmRevealToken(id)
+                    } else {
+                        Message::None
+                    }
+                })
+            },
+            Message::ConfirmRevealToken(id) => {
+                // Only while that bot's view is still the one open.
+                if self.viewing_bot_chat == Some(id) {
+                    self.revealed_token = Some(id);
+                }
+                Task::none()
+            },
+            Message::HideToken => {
+                self.revealed_token = None;
+                Task::none()
+            },
+            Message::ToggleBurstLimit(enabled) => {
+                self.burst_limiter.enabled = enabled;
+                self.burst_holding = false;
+                Task::none()
+            },
+            Message::BurstMaxMessagesUpdated(value) => {
+                if let Ok(val) = value.parse::<usize>()
+                    && val > 0 {
+                    self.burst_limiter.max_messages = val;
+                }
+                Task::none()
+            },
+            Message::MaxRetriesUpdated(value) => {
+                if let Ok(val) = value.parse::<u32>()
+                    && val <= MAX_SEND_RETRIES {
+                    self.max_send_retries = val;
+                }
+                Task::none()
+            },
+            Message::BurstWindowUpdated(value) => {
+                if let Ok(val) = value.parse::<u64>()
+                    && val > 0 {
+                    self.burst_limiter.window = Duration::from_secs(val);
+                }
+                Task::none()
+            },
+            Message::ToggleShowSummaryOnExit(enabled) => {
+                self.show_summary_on_exit = enabled;
+                Task::none()
+            },
+            Message::WindowCloseRequested(id) => {
+                self.settings.channel = self.channel.clone();
+                self.settings.send = Some(self.current_preset());
+                if let Err(e) = self.settings.save() {
+                    log::error!("Failed to save settings: {}", e);
+                }
+
+                let summary = self.session.summary();
+                let saved = self.session.save_summary();
+
+                if self.show_summary_on_exit {
+                    let description = match &saved {
+                        Ok(path) => format!("{}\n\nSaved to {}", summary, path.display()),
+                        Err(e) => format!("{}\n\nCould not save summary: {}", summary, e),
+                    };
+
+                    MessageDialog::new()
+                        .set_level(MessageLevel::Info)
+                        .set_title("Session summary")
+                        .set_description(description)
+                        .set_buttons(MessageButtons::Ok)
+                        .show();
+                }
+
+                iced::window::close(id)
+            },
+            Message::ToggleShowBots(show) => {
+                self.settings.layout.show_bots = show;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleShowEditor(show) => {
+                self.settings.layout.show_editor = show;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleShowChat(show) => {
+                self.settings.layout.show_chat = show;
+                if let Err(e) = self.settings.save() {
+                    self.chat_history.push(ChatEntry::error(format!("Failed to save settings: {}", e)));
+                }
+                Task::none()
+            },
+            Message::ToggleControlApi(enabled) => {
+                self.control_api_enabled = enabled;
+                Task::none()
+            },
+            Message::ControlApiPortUpdated(value) => {
+                self.control_api_port_input = value;
+                Task::none()
+            },
+            Message::ApplyControlApiPort => {
+                match self.control_api_port_input.trim().parse::<u16>() {
+                    Ok(port) if port > 0 => self.control_api_port = port,
+                    _ => {
+                        self.chat_history.push(ChatEntry::error(format!("Invalid control API port: {}", self.control_api_port_input)));
+                        self.control_api_port_input = self.control_api_port.to_string();
+                    }
+                }
+                Task::none()
+            },
+            Message::RegenerateControlApiToken => {
+                self.control_api_token = generate_api_token();
+                Task::none()
+            },
+            Message::ControlApi(ApiEvent::Request(request, responder)) => {
+                let (response, task) = self.handle_api_request(request);
+                responder.respond(response);
+                task
+            },
+            Message::ControlApi(ApiEvent::Failed(error)) => {
+                self.control_api_enabled = false;
+                self.chat_history.push(ChatEntry::error(error));
+                Task::none()
+            },
+            Message::SelectActiveBot(id) => {
+                self.selected_bot = if self.selected_bot == Some(id) {
+                    None
+                } else {
+                    Some(id)
+                };
+                Task::none()
+            },
+        }
+    }
+
+    fn export_history_task(&self, entries: Vec<ChatEntry>, file_name: &str) -> Task<Message> {
+        let file = FileDialog::new()
+            .add_filter("Text Document", &["txt"])
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.txt", file_name));
+
+        let Some(path) = file.save_file() else {
+            return Task::none();
+        };
+
+        Task::perform(
+            async move {
+                export_history(&path, &entries).map(|_| path).map_err(|e| e.to_string())
+            },
+            Message::HistoryExported
+        )
+    }
+
+    /// Adds a send to the global and the bot's history, one entry for each
+    /// `(channel, message)` when it went to several channels. Returns the
+    /// ids of the entries.
+    fn log_send(&mut self, index: usize, action: u64, random: bool, sends: &[(String, String)]) -> HashSet<u64> {
+        let Some(bot) = self.bots.get_mut(index) else {
+            return HashSet::new();
+        };
+        let multi = sends.len() > 1;
+        let entries: Vec<ChatEntry> = sends.iter().map(|(channel, message)| {
+            let entry = if random {
+                ChatEntry::random_sent(bot.id, bot.name.clone(), message.clone())
+            } else {
+                ChatEntry::sent(bot.id, bot.name.clone(), message.clone())
+            };
+            if multi { entry.with_channel(channel.clone()) } else { entry }
+        }).collect();
+
+        bot.last_sent = Some(Instant::now());
+        let ids: HashSet<u64> = entries.iter().map(|entry| entry.id).collect();
+        self.last_action.log(action, ids.iter().copied());
+        for entry in entries {
+            bot.add_to_history(entry.clone());
+            self.chat_history.push(entry);
+        }
+        ids
+    }
+
+    /// Gets `message` ready to go out from the bot at `index` to each
+    /// channel in `channel`: fills in the template per channel, drops what
+    /// the blocklist or length limit stops, splits it, takes burst slots
+    /// and logs it. `None` when nothing is left to send.
+    fn stage_send(&mut self, index: usize, channel: &str, action: u64, random: bool, message: &str) -> Option<StagedSend> {
+        let bot = self.bots.get(index)?;
+        let rendered: Vec<(String, String)> = parse_channels(channel)
+            .into_iter()
+            .map(|channel| {
+                let message = self.render_template(bot, &channel, message);
+                (channel, message)
+            })
+            .collect();
+
+        let mut sends = Vec::new();
+        for (channel, message) in rendered {
+            if random && self.is_held_offline(&channel) {
+                continue;
+            }
+            let Some(message) = self.screen_message(message) else {
+                continue;
+            };
+            if self.blocks_send(&message) {
+                self.chat_history.push(ChatEntry::error(format!(
+                    "Not sent to {}: over {} characters and auto-split is off",
+                    channel, MAX_MESSAGE_LENGTH
+                )));
+                continue;
+            }
+            sends.push((channel, message));
+        }
+        if sends.is_empty() {
+            return None;
+        }
+
+        let payloads: Vec<(String, Vec<String>)> = sends
+            .iter()
+            .map(|(channel, message)| (channel.clone(), self.prepare_message(message)))
+            .collect();
+        let slots = payloads.iter().map(|(_, parts)| parts.len()).sum();
+        if !self.begin_send(slots) {
+            return None;
+        }
+
+        for (channel, _) in &sends {
+            self.session.record_channel(channel);
+        }
+        let entries = self.log_send(index, action, random, &sends);
+
+        let bot = self.bots.get(index)?;
+        Some(StagedSend {
+            bot_id: bot.id,
+            action,
+            credentials: bot.credentials(self.settings.timeouts, self.settings.use_tls),
+            payloads,
+            entries,
+            slots,
+        })
+    }
+
+    /// Starts a staged send, after `delay` seconds when that is above zero.
+    /// Mode changes call off sends that are still waiting.
+    fn dispatch_send(&mut self, staged: StagedSend, delay: u64) -> Task<Message> {
+        let StagedSend { bot_id, action, credentials, payloads, entries, slots } = staged;
+        let max_retries = self.max_send_retries;
+        let gate = self.delay_gate.clone();
+
+        self.track_send(bot_id, action, (entries, slots), |send| Task::perform(
+            async move {
+                if delay > 0 && !gate.wait(Duration::from_secs(delay)).await {
+                    return Ok(None);
+                }
+                credentials.send_message(&payloads, max_retries).await.map(Some)
+            },
+            move |result| {
+                match result {
+                    Ok(Some(delivery)) => Message::MessageSent(bot_id, send, Ok(delivery)),
+                    Ok(None) => Message::SendDropped(bot_id, send),
+                    Err(e) => Message::MessageSent(bot_id, send, Err(e.to_string())),
+                }
+            }
+        ))
+    }
+
+    /// The channel a bot sends to: its own override, or the global channel.
+    fn bot_channel(&self, bot: &Bot) -> String {
+        bot.channel.clone().unwrap_or_else(|| self.channel.clone())
+    }
+
+    /// Picks the bot used by Enter-to-send and the single-send hotkey,
+    /// according to the configured single-send policy.
+    fn pick_single_bot(&self) -> Option<usize> {
+        let available = self.bots
+            .iter()
+            .enumerate()
+            .filter(|(_, bot)| bot.is_available() && bot.enable);
+
+        match self.single_send_policy {
+            SingleSendPolicy::FirstAvailable => available.map(|(idx, _)| idx).next(),
+            SingleSendPolicy::RandomAvailable => {
+                // Healthier bots are picked more often; every available bot keeps some chance.
+                let weighted: Vec<(usize, u32)> = available
+                    .map(|(idx, bot)| (idx, bot.health_score(&self.settings.health_weights).max(1) as u32))
+                    .collect();
+                let total: u32 = weighted.iter().map(|(_, weight)| weight).sum();
+                if total == 0 {
+                    return None;
+                }
+
+                let mut roll = rand::rng().random_range(0..total);
+                for (idx, weight) in weighted {
+                    if roll < weight {
+                        return Some(idx);
+                    }
+                    roll -= weight;
+                }
+                None
+            },
+            SingleSendPolicy::LeastRecentlyUsed => available
+                .min_by_key(|(_, bot)| bot.last_sent)
+                .map(|(idx, _)| idx),
+            SingleSendPolicy::RoundRobin => {
+                let available: Vec<usize> = available.map(|(idx, _)| idx).collect();
+                available
+                    .iter()
+                    .find(|&&idx| idx >= self.round_robin_cursor)
+                    .or(available.first())
+                    .copied()
+            },
+            SingleSendPolicy::SelectedBot => self.selected_bot
+                .and_then(|id| self.bot_index(id))
+                .filter(|&idx| self.bots.get(idx).is_some_and(|bot| bot.is_available() && bot.enable)),
+        }
+    }
+
+    /// Picks the bot for a single send and moves the round-robin cursor
+    /// past it.
+    fn take_single_bot(&mut self) -> Option<usize> {
+        let index = self.pick_single_bot()?;
+        self.round_robin_cursor = index + 1;
+        Some(index)
+    }
+
+    /// Indices of the bots matching the search, which the bulk enable
+    /// buttons act on.
+    fn filtered_bot_indices(&self) -> Vec<usize> {
+        self.get_filtered_bots().into_iter().map(|(index, _)| index).collect()
+    }
+
+    fn get_filtered_bots(&self) -> Vec<(usize, &Bot)> {
+        if self.search_query.is_empty() {
+            self.bots.iter().enumerate().collect()
+        } else {
+            self.bots
+                .iter()
+                .enumerate()
+                .filter(|(_, bot)| {
+                    bot.name.to_lowercase().contains(&self.search_query.to_lowercase())
+                })
+                .collect()
+        }
+    }
+
+    /// Messages that are enabled and off cooldown, with their selection weight.
+    fn ready_messages(&self) -> Vec<(String, u32)> {
+        let now = Instant::now();
+
+        self.messages
+            .iter()
+            .filter_map(|text| match self.message_meta.get(text) {
+                Some(entry) if entry.is_ready(now) => Some((text.clone(), entry.weight)),
+                Some(_) => None,
+                None => Some((text.clone(), 1)),
+            })
+            .filter(|(text, _)| !self.is_rejected_command(text))
+            .collect()
+    }
+
+    fn pick_random_message(&mut self, ready: &[(String, u32)], rng: &mut impl Rng) -> String {
+        if self.no_repeats {
+            self.draw_from_bag(ready, rng)
+        } else {
+            self.pick_weighted_message(ready, rng)
+        }
+    }
+
+    /// Draws the next message from the shuffle bag, refilling it once every
+    /// ready message had its turn. Weights don't apply here; each message
+    /// comes up once per cycle.
+    fn draw_from_bag(&mut self, ready: &[(String, u32)], rng: &mut impl Rng) -> String {
+        let is_ready = |text: &String| ready.iter().any(|(ready, _)| ready == text);
+        if !self.message_bag.iter().any(is_ready) {
+            self.message_bag = ready.iter().map(|(text, _)| text.clone()).collect();
+            self.message_bag.shuffle(rng);
+            // Drawn from the back, so a new cycle mustn't start with the
+            // message that ended the last one.
+            let last = self.message_bag.len() - 1;
+            if last > 0 && self.message_bag.last() == self.last_bag_message.as_ref() {
+                self.message_bag.swap(0, last);
+            }
+        }
+
+        // Not-ready messages wait in the bag for a later draw.
+        let position = self.message_bag.iter().rposition(is_ready).unwrap_or_default();
+        let message = self.message_bag.remove(position);
+        self.last_bag_message = Some(message.clone());
+        self.mark_message_used(&message);
+        message
+    }
+
+    fn pick_weighted_message(&mut self, ready: &[(String, u32)], rng: &mut impl Rng) -> String {
+        // Summed as u64 so many large weights can't overflow.
+        let total: u64 = ready.iter().map(|(_, weight)| *weight as u64).sum();
+        let mut roll = rng.random_range(0..total);
+
+        let mut chosen = &ready[ready.len() - 1].0;
+        for (text, weight) in ready {
+            let weight = *weight as u64;
+            if roll < weight {
+                chosen = text;
+                break;
+            }
+            roll -= weight;
+        }
+
+        let message = chosen.clone();
+        self.mark_message_used(&message);
+        message
+    }
+
+    fn mark_message_used(&mut self, message: &str) {
+        if let Some(entry) = self.message_meta.get_mut(message) {
+            entry.last_used = Some(Instant::now());
+        }
+    }
+
+    /// How many bots a send from all of them would use, and how many
+    /// channels it would reach.
+    fn mass_send_targets(&self) -> (usize, usize) {
+        let bots: Vec<&Bot> = self.bots.iter().filter(|bot| bot.is_available() && bot.enable).collect();
+        let channels: HashSet<String> = bots
+            .iter()
+            .map(|bot| self.bot_channel(bot).to_lowercase())
+            .collect();
+        (bots.len(), channels.len())
+    }
+
+    /// Asks before a send from many bots, when the user turned that on.
+    /// `what` names the send in the question, e.g. `"hello"` or
+    /// `a random message`. Gives the task showing the question, or `None`
+    /// if the send can go ahead now. A yes replays `kind` through
+    /// `ConfirmMassSend`, which lets it past this check once.
+    fn confirm_mass_send(&mut self, kind: MassSend, what: &str, (bots, channels): (usize, usize)) -> Option<Task<Message>> {
+        if !self.settings.confirm_mass_sends || self.mass_send_confirmed.take() == Some(kind) {
+            return None;
+        }
+
+        let dialog = AsyncMessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Confirm mass send")
+            .set_description(format!(
+                "Send {} from {} bots to {} channels?",
+                what,
+                bots,
+                channels
+            ))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        Some(Task::perform(dialog, move |result| {
+            if result == MessageDialogResult::Yes {
+                Message::ConfirmMassSend(kind)
+            } else {
+                Message::CancelMassSend
+            }
+        }))
+    }
+
+    /// Sends `message` from every available bot, to `channel` when given or
+    /// else to each bot's own channel. Gives the bots that took the send,
+    /// or `None` if none did.
+    fn send_with_all_bots(&mut self, channel: Option<String>, action: u64, message: String) -> Option<(Task<Message>, HashSet<u64>)> {
+        if self.reject_command(&message) {
+            return None;
+        }
+
+        let available_bots: Vec<usize> = self.bots
+            .iter()
+            .enumerate()
+            .filter(|(_, bot)| bot.is_available() && bot.enable)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        if available_bots.is_empty() {
+            return None;
+        }
+
+        let mut tasks = Vec::new();
+        let mut bots = HashSet::new();
+        let mut rng = rand::rng();
+
+        for (delay_index, &bot_index) in available_bots.iter().enumerate() {
+            let channel = channel.clone().unwrap_or_else(|| self.bot_channel(&self.bots[bot_index]));
+            let delay = if self.simultaneous_mode {
+                0
+            } else {
+                rng.random_range(self.min_bot_delay..=self.max_bot_delay) * delay_index as u64
+            };
+            match self.stage_send(bot_index, &channel, action, false, &message) {
+                Some(staged) => {
+                    bots.insert(staged.bot_id);
+                    tasks.push(self.dispatch_send(staged, delay));
+                }
+                None if self.burst_holding => break,
+                None => {}
+            }
+        }
+
+        if tasks.is_empty() {
+            return None;
+        }
+        Some((Task::batch(tasks), bots))
+    }
+
+    fn send_from_bot(&mut self, index: usize, channel: String, action: u64, message: String) -> Option<Task<Message>> {
+        if self.reject_command(&message) {
+            return None;
+        }
+
+        if !self.bots.get(index).is_some_and(|bot| bot.is_available() && bot.enable) {
+            return None;
+        }
+
+        let staged = self.stage_send(index, &channel, action, false, &message)?;
+        Some(self.dispatch_send(staged, 0))
+    }
+
+    /// Why a blast staged nothing. A message that only gets blocked once a
+    /// template is filled in for a bot ends up in the last case.
+    fn blast_refusal(&self, message: &str) -> ApiResponse {
+        let blocked = self.is_rejected_command(message)
+            || self.blocks_send(message)
+            || (!self.settings.mask_blocked && find_blocked(message, &self.settings.blocklist).is_some());
+        if blocked {
+            return ApiResponse::error(422, "message was blocked");
+        }
+        if !self.bots.iter().any(|bot| bot.is_available() && bot.enable) {
+            return ApiResponse::error(409, "no available bots");
+        }
+        if self.burst_holding {
+            return ApiResponse::error(429, "global limit reached");
+        }
+        ApiResponse::error(422, "message was blocked")
+    }
+
+    fn handle_api_request(&mut self, request: ApiRequest) -> (ApiResponse, Task<Message>) {
+        match request {
+            ApiRequest::Send(request) => {
+                let Some(index) = self.bots.iter().position(|bot| bot.name == request.bot) else {
+                    return (ApiResponse::error(404, "unknown bot"), Task::none());
+                };
+
+                let channel = request.channel.unwrap_or_else(|| self.channel.clone());
+                if channel.is_empty() || request.message.is_empty() {
+                    return (ApiResponse::error(400, "channel and message are required"), Task::none());
+                }
+
+                let action = self.begin_action();
+                match self.send_from_bot(index, channel, action, request.message) {
+                    Some(task) => (ApiResponse::accepted(), task),
+                    None => (ApiResponse::error(409, "bot is unavailable or the global limit was reached"), Task::none()),
+                }
+            },
+            ApiRequest::Blast(request) => {
+                if self.channel.is_empty() || request.message.is_empty() {
+                    return (ApiResponse::error(400, "channel and message are required"), Task::none());
+                }
+
+                let action = self.begin_action();
+                match self.send_with_all_bots(None, action, request.message.clone()) {
+                    Some((task, _)) => (ApiResponse::accepted(), task),
+                    None => (self.blast_refusal(&request.message), Task::none()),
+                }
+            },
+            ApiRequest::Status => {
+                let bots: Vec<_> = self.bots
+                    .iter()
+                    .map(|bot| json!({
+                        "name": bot.name,
+                        "available": bot.is_available(),
+                        "enabled": bot.enable,
+                    }))
+                    .collect();
+
+                (ApiResponse::ok(json!({
+                    "channel": self.channel,
+                    "random_messages_enabled": self.random_messages_enabled,
+                    "global_limit_holding": self.burst_holding,
+                    "bots": bots,
+                })), Task::none())
+            },
+        }
+    }
+
+    /// Makes a bot's send cancellable and counts it as pending until its
+    /// `MessageSent` arrives. `task` gets the key the send reports back with.
+    fn track_send(&mut self, id: u64, action: u64, logged: (HashSet<u64>, usize), task: impl FnOnce(u64) -> Task<Message>) -> Task<Message> {
+        let send = self.next_send;
+        self.next_send += 1;
+        let (task, handle) = task(send).abortable();
+        let queued = self.pending_count(id);
+        let pending = self.pending_sends.entry(id).or_default();
+        pending.handles.insert(send, handle);
+        pending.logged.insert(send, logged);
+        self.last_action.track(action, send, id);
+
+        if let Some(index) = self.bot_index(id)
+            && let bot = &mut self.bots[index]
+            && bot.available_sends() <= queued {
+            let entry = ChatEntry::info("⏳ Rate-limited, queued");
+            bot.add_to_history(entry.clone());
+            self.chat_history.push(entry.with_bot(bot.id, bot.name.clone()));
+        }
+        task
+    }
+
+    fn finish_pending(&mut self, id: u64, send: u64) {
+        if let Some(pending) = self.pending_sends.get_mut(&id) {
+            pending.handles.remove(&send);
+            pending.logged.remove(&send);
+            if pending.handles.is_empty() {
+                self.pending_sends.remove(&id);
+            }
+        }
+    }
+
+    /// Takes back a send that was called off before it went out: its
+    /// history lines and the burst slots it held.
+    fn roll_back_send(&mut self, id: u64, send: u64) {
+        let Some((entries, slots)) = self.pending_sends
+            .get_mut(&id)
+            .and_then(|pending| pending.logged.remove(&send)) else {
+            return;
+        };
+
+        self.chat_history.remove_ids(&entries);
+        if let Some(bot) = self.bot_by_id_mut(id) {
+            bot.chat_history.remove_ids(&entries);
+        }
+        self.last_action.forget(send, &entries);
+        self.burst_limiter.release(slots);
+    }
+
+    /// Drops sends still waiting out their per-bot delay, which were
+    /// scheduled under settings that just changed.
+    fn cancel_delayed_sends(&mut self) {
+        let cancelled = self.delay_gate.cancel();
+        if cancelled > 0 {
+            self.chat_history.push(ChatEntry::info(format!("✖ Cancelled {} pending delayed sends", cancelled)));
+        }
+    }
+
+    fn pending_count(&self, id: u64) -> usize {
+        self.pending_sends.get(&id).map_or(0, |pending| pending.handles.len())
+    }
+
+    /// Opens a new send action. Every send a user entry point stages is
+    /// tagged with its id, so undo takes them back together.
+    fn begin_action(&mut self) -> u64 {
+        self.next_action += 1;
+        self.next_action
+    }
+
+    /// Deletes the given `(bot, channel, id)` messages from Twitch chat, for
+    /// the bots that are moderators.
+    fn delete_sent_messages(&self, messages: &[(u64, String, String)]) -> Vec<Task<Message>> {
+        messages
+            .iter()
+            .filter_map(|(bot_id, channel, message_id)| {
+                let bot = self.bot_by_id(*bot_id).filter(|bot| bot.moderator)?;
+                let credentials = bot.credentials(self.settings.timeouts, self.settings.use_tls);
+                let (bot_id, channel, message_id) = (*bot_id, channel.clone(), message_id.clone());
+                Some(Task::perform(
+                    async move { credentials.delete_message(&channel, &message_id).await },
+                    move |result| Message::UndoDeleted(bot_id, result.map_err(|e| e.to_string()))
+                ))
+            })
+            .collect()
+    }
+
+    /// Aborts the bot's pending sends and takes them back like a dropped
+    /// send: their history lines, their burst slots, and the cleared input
+    /// if it was waiting on them.
+    fn cancel_pending_sends(&mut self, id: u64) {
+        let Some(sends) = self.pending_sends
+            .get(&id)
+            .map(|pending| pending.handles.keys().copied().collect::<Vec<_>>()) else {
+            return;
+        };
+        for &send in &sends {
+            self.roll_back_send(id, send);
+            self.undone_sends.remove(&send);
+        }
+        if let Some(pending) = self.pending_sends.remove(&id) {
+            pending.handles.values().for_each(task::Handle::abort);
+        }
+        self.settle_cleared_message(id, false);
+
+        if let Some(index) = self.bot_index(id) {
+            let bot = &mut self.bots[index];
+            bot.add_to_history(ChatEntry::info(format!("✖ Cancelled {} pending sends", sends.len())));
+        }
+    }
+
+    /// Where the bot with `id` currently sits in the list. Positions change
+    /// when bots are sorted or removed, ids don't.
+    fn bot_index(&self, id: u64) -> Option<usize> {
+        self.bots.iter().position(|bot| bot.id == id)
+    }
+
+    fn bot_by_id(&self, id: u64) -> Option<&Bot> {
+        self.bots.iter().find(|bot| bot.id == id)
+    }
+
+    fn bot_by_id_mut(&mut self, id: u64) -> Option<&mut Bot> {
+        self.bots.iter_mut().find(|bot| bot.id == id)
+    }
+
+    /// Called once per outgoing send. Takes a slot from the global burst
+    /// limiter for each of its `slots` PRIVMSGs; when the limit is hit, a
+    /// warning is pushed once and sends are held until the window frees up.
+    fn begin_send(&mut self, slots: usize) -> bool {
+        if self.burst_limiter.try_acquire(slots) {
+            self.burst_holding = false;
+            return true;
+        }
+
+        if !self.burst_holding {
+            self.burst_holding = true;
+            self.chat_history.push(ChatEntry::info(format!(
+                "⚠️ Global limit reached ({} messages per {} sec), holding",
+                self.burst_limiter.max_messages,
+                self.burst_limiter.window.as_secs()
+            )));
+        }
+        false
+    }
+
+    /// Replaces the message list. Very large lists only put the first
+    /// `EDITOR_LINE_LIMIT` lines in the editor, since building editor
+    /// content is what stalls the UI; every line is still used for sending.
+    fn set_messages(&mut self, messages: Vec<String>) {
+        self.loading_messages = false;
+        self.editor_truncated = messages.len() > EDITOR_LINE_LIMIT;
+
+        let shown = &messages[..messages.len().min(EDITOR_LINE_LIMIT)];
+        self.messages_editor = text_editor::Content::with_text(&shown.join("\n"));
+        self.selected_editor_line = None;
+
+        self.chat_history.push(ChatEntry::info(format!("📄 Loaded {} messages", format_count(messages.len()))));
+        self.messages = messages;
+        self.long_lines = long_line_numbers(&self.messages);
+        self.message_bag.clear();
+    }
+
+    /// The lines of `content` with the metadata they were loaded with, for
+    /// writing back to CSV or JSON. Lines added since get the defaults.
+    fn message_entries(&self, content: &str) -> Vec<MessageEntry> {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match self.message_meta.get(line) {
+                Some(entry) => entry.clone(),
+                None => MessageEntry::new(line),
+            })
+            .collect()
+    }
+
+    /// Parses the multi-channel editor, one `channel: message` per line.
+    /// A channel listed twice keeps its last message.
+    fn parse_channel_matrix(&self) -> Result<HashMap<String, String>, String> {
+        let mut matrix = HashMap::new();
+
+        for (number, line) in self.channel_matrix_editor.text().lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((channel, message)) = line.split_once(':') else {
+                return Err(format!("Multi-channel line {}: expected \"channel: message\"", number + 1));
+            };
+
+            let channel = channel.trim().trim_start_matches('#').to_lowercase();
+            let message = message.trim();
+            if !channel.is_empty() && !message.is_empty() {
+                matrix.insert(channel, message.to_string());
+            }
+        }
+
+        Ok(matrix)
+    }
+
+    fn channel_matrix_settings(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_channel_matrix {
+            return column![].into();
+        }
+
+        let controls = row![
+            checkbox(self.channel_matrix_all_bots)
+                .on_toggle(Message::ToggleChannelMatrixAllBots),
+            text("Send with all bots (otherwise one bot per channel)").size(14),
+            button(text("📢 Send All"))
+                .on_press_maybe(
+                    self.bots.iter().any(|b| b.is_available() && b.enable)
+                        .then_some(Message::SendChannelMatrix)
+                )
+                .padding(8),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        container(
+            column![
+                text("One channel per line as \"channel: message\". All channels are sent at once.")
+                    .size(12)
+                    .style(move |_| text::Style {
+                        color: Some(palette.text_muted)
+                    }),
+                text_editor(&self.channel_matrix_editor)
+                    .on_action(Message::ChannelMatrixAction)
+                    .height(Length::Fixed(120.0)),
+                controls,
+            ]
+            .spacing(8)
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel)),
+            border: Border {
+                radius: 6.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn stats_panel(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_stats {
+            return column![].into();
+        }
+
+        let sent: usize = self.bot_stats.values().map(|stats| stats.sent).sum();
+        let failed: usize = self.bot_stats.values().map(|stats| stats.failed).sum();
+        let received: usize = self.bot_stats.values().map(|stats| stats.received).sum();
+        let success = (sent * 100)
+            .checked_div(sent + failed)
+            .map_or("-".to_string(), |rate| format!("{}%", rate));
+
+        // Busiest bots first by default, so the load balance shows at a glance.
+        let sort = self.stats_sort;
+        let mut rows: Vec<(&Bot, BotStats)> = self.bots
+            .iter()
+            .filter_map(|bot| self.bot_stats.get(&bot.id).map(|stats| (bot, *stats)))
+            .collect();
+        rows.sort_by(|a, b| sort.value(&b.1).cmp(&sort.value(&a.1)).then_with(|| a.0.name.cmp(&b.0.name)));
+
+        let mut per_bot = column![].spacing(2);
+        for (bot, stats) in rows {
+            per_bot = per_bot.push(
+                row![
+                    with_full_text_tooltip(&bot.name, BOT_NAME_MAX_WIDTH, 13),
+                    text(format!("{} sent", stats.sent)).size(13),
+                    text(format!("{} failed", stats.failed))
+                        .size(13)
+                        .style(move |_| text::Style {
+                            color: Some(if stats.failed > 0 { palette.error_text } else { palette.text_muted })
+                        }),
+                    text(format!("{} mentions", stats.received)).size(13),
+                ]
+                .spacing(15)
+            );
+        }
+
+        container(
+            column![
+                row![
+                    text(format!("Sent: {} · Failed: {} · Success: {} · Mentions: {}", sent, failed, success, received)).size(14),
+                    pick_list(StatsSort::ALL, Some(self.stats_sort), Message::SortStats)
+                        .text_size(12),
+                    button(text("Reset").size(12))
+                        .on_press_maybe((!self.bot_stats.is_empty()).then_some(Message::ResetStats))
+                        .padding(5),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                scrollable(per_bot).height(Length::Fixed(140.0)),
+            ]
+            .spacing(8)
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel)),
+            border: Border {
+                radius: 6.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    /// Parses the sequence editor, one `bot: message` step per line.
+    fn parse_sequence(&self) -> Result<Vec<(u64, String)>, String> {
+        let mut steps = Vec::new();
+
+        for (number, line) in self.sequence_editor.text().lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((name, message)) = line.split_once(':') else {
+                return Err(format!("Sequence line {}: expected \"bot: message\"", number + 1));
+            };
+
+            let name = name.trim();
+            let Some(bot) = self.bots.iter().find(|bot| bot.name.eq_ignore_ascii_case(name)) else {
+                return Err(format!("Sequence line {}: unknown bot \"{}\"", number + 1, name));
+            };
+
+            let message = message.trim();
+            if !message.is_empty() {
+                steps.push((bot.id, message.to_string()));
+            }
+        }
+
+        Ok(steps)
+    }
+
+    fn sequence_settings(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_sequence_editor {
+            return column![].into();
+        }
+
+        let status = match &self.sequence_run {
+            Some(run) => format!("▶️ Step {}/{}", run.next, run.steps.len()),
+            None => String::new(),
+        };
+
+        let controls = row![
+            text("Gap between steps (sec):").size(14),
+            text_input("", &self.sequence_gap.to_string())
+                .on_input(Message::SequenceGapUpdated)
+                .padding(5)
+                .width(Length::Fixed(80.0)),
+            if self.sequence_run.is_some() {
+                button(text("⏹️ Stop"))
+                    .on_press(Message::StopSequence)
+                    .padding(8)
+            } else {
+                button(text("▶️ Run Sequence"))
+                    .on_press_maybe((!self.channel.is_empty()).then_some(Message::StartSequence))
+                    .padding(8)
+            },
+            text(status).size(14),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        container(
+            column![
+                text("One step per line as \"bot: message\". Steps run in order, each after the previous one is sent.")
+                    .size(12)
+                    .style(move |_| text::Style {
+                        color: Some(palette.text_muted)
+                    }),
+                text_editor(&self.sequence_editor)
+                    .on_action(Message::SequenceEditorAction)
+                    .height(Length::Fixed(120.0)),
+                controls,
+            ]
+            .spacing(8)
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel)),
+            border: Border {
+                radius: 6.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    /// Sends the scheduled messages that are due and moves repeating ones
+    /// to their next time.
+    fn run_due_schedules(&mut self) -> Task<Message> {
+        let now = chrono::Local::now();
+        if !self.schedules.iter().any(|schedule| schedule.is_due(now)) {
+            return Task::none();
+        }
 
-                    let history_msg = format!("[{}] {}", bot.name, message);
-                    self.chat_history.push(history_msg.clone());
-                    
-                    if let Some(bot_mut) = self.bots.get_mut(index) {
-                        bot_mut.add_to_history(history_msg);
-                    }
-                    
-                    self.bot_message_input.clear();
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.schedules)
+            .into_iter()
+            .partition(|schedule| schedule.is_due(now));
+        self.schedules = waiting;
 
-                    Task::perform(
-                        async move {
-                            bot_clone.send_message(&channel, &message).await
-                        },
-                        move |result| {
-                            match result {
-                                Ok(_) => Message::MessageSent(index, Ok(())),
-                                Err(e) => Message::MessageSent(index, Err(e.to_string())),
-                            }
-                        }
-                    )
-                } else {
-                    Task::none()
-                }
-            },
-            Message::ClearBotHistory(index) => {
-                if let Some(bot) = self.bots.get_mut(index) {
-                    bot.clear_history();
-                }
-                Task::none()
-            },
-            Message::ClearGlobalHistory => {
-                self.chat_history.clear();
-                Task::none()
-            },
+        let action = self.begin_action();
+        let mut tasks = Vec::new();
+        for mut schedule in due {
+            let index = match schedule.bot {
+                Some(id) => self.bot_index(id),
+                None => self.pick_single_bot(),
+            };
+            let channel = index.map(|index| self.bot_channel(&self.bots[index])).unwrap_or_default();
+            let task = index
+                .filter(|_| !channel.is_empty())
+                .and_then(|index| self.send_from_bot(index, channel, action, schedule.text.clone()));
 
-            Message::ClearAllHistory => {
-                self.chat_history.clear();
-                for bot in &mut self.bots {
-                    bot.clear_history();
-                }
-                Task::none()
-            },
+            match task {
+                Some(task) => tasks.push(task),
+                None => self.chat_history.push(ChatEntry::info(format!(
+                    "⏰ Skipped scheduled message \"{}\": no bot could send it",
+                    schedule.text
+                ))),
+            }
+            if schedule.advance(now) {
+                self.schedules.push(schedule);
+            }
+        }
 
-            Message::SearchQueryUpdated(query) => {
-                self.search_query = query;
-                Task::none()
+        self.schedules.sort_by_key(|schedule| schedule.at);
+        Task::batch(tasks)
+    }
+
+    fn advanced_settings(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_advanced {
+            return column![].into();
+        }
+
+        let mut fields = row![].spacing(10).align_y(Alignment::Center);
+        let mut timeouts = self.settings.timeouts;
+        for field in TimeoutField::ALL {
+            fields = fields.push(text(format!("{} (ms):", field)).size(14));
+            fields = fields.push(
+                text_input("", &field.slot(&mut timeouts).to_string())
+                    .on_input(move |value| Message::TimeoutUpdated(field, value))
+                    .on_submit(Message::SaveSettings)
+                    .padding(5)
+                    .width(Length::Fixed(70.0))
+            );
+        }
+
+        container(
+            column![
+                text("Network waits. Raise them on a slow connection, lower them to fail faster.")
+                    .size(12)
+                    .style(move |_| text::Style {
+                        color: Some(palette.text_muted)
+                    }),
+                fields,
+                row![
+                    button(text("Reset to defaults").size(12))
+                        .on_press_maybe((self.settings.timeouts != Timeouts::default()).then_some(Message::ResetTimeouts))
+                        .padding(5),
+                    button(text("📜 Open Log File").size(12))
+                        .on_press(Message::OpenLogFile)
+                        .padding(5),
+                ]
+                .spacing(10),
+            ]
+            .spacing(8)
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel)),
+            border: Border {
+                radius: 6.0.into(),
+                ..Default::default()
             },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn schedule_settings(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_schedule {
+            return column![].into();
+        }
 
-            Message::MessageClicked(message_index) => {
-                if let Some(msg) = self.chat_history.get(message_index) {
+        let any_bot = BotChoice { id: None, name: String::new() };
+        let choices: Vec<BotChoice> = std::iter::once(any_bot.clone())
+            .chain(self.bots.iter().map(|bot| BotChoice { id: Some(bot.id), name: bot.name.clone() }))
+            .collect();
+        let selected = choices
+            .iter()
+            .find(|choice| choice.id == self.schedule_bot)
+            .cloned()
+            .unwrap_or(any_bot);
 
-                    if let Some(bot_name) = self.extract_bot_name_from_message(msg) {
-                        if let Some(bot_index) = self.bots.iter().position(|b| b.name == bot_name) {
-                            self.viewing_bot_chat = Some(bot_index);
-                            self.bot_message_input.clear();
-                        }
-                    }
-                }
-                Task::none()
+        let can_add = !self.schedule_time.trim().is_empty() && !self.schedule_text.trim().is_empty();
+        let controls = row![
+            text_input("14:30", &self.schedule_time)
+                .on_input(Message::ScheduleTimeUpdated)
+                .padding(5)
+                .width(Length::Fixed(80.0)),
+            text_input("Message", &self.schedule_text)
+                .on_input(Message::ScheduleTextUpdated)
+                .on_submit_maybe(can_add.then_some(Message::AddSchedule))
+                .padding(5),
+            pick_list(choices, Some(selected), Message::ScheduleBotSelected)
+                .text_size(14),
+            text("Every (min):").size(14),
+            text_input("once", &self.schedule_repeat)
+                .on_input(Message::ScheduleRepeatUpdated)
+                .padding(5)
+                .width(Length::Fixed(60.0)),
+            button(text("➕ Add"))
+                .on_press_maybe(can_add.then_some(Message::AddSchedule))
+                .padding(8),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let mut list = column![].spacing(4);
+        for (index, schedule) in self.schedules.iter().enumerate() {
+            let bot = match schedule.bot {
+                Some(id) => self.bot_by_id(id).map_or("removed bot".to_string(), |bot| bot.name.clone()),
+                None => "any bot".to_string(),
+            };
+            let repeat = schedule
+                .repeat
+                .map_or(String::new(), |repeat| format!(" · every {} min", repeat.num_minutes()));
+
+            list = list.push(
+                row![
+                    text(format!("{} · {}{}", schedule.at.format("%a %H:%M"), bot, repeat))
+                        .size(13)
+                        .style(move |_| text::Style {
+                            color: Some(palette.text_muted)
+                        }),
+                    with_full_text_tooltip(&schedule.text, CHAT_PREVIEW_MAX_WIDTH / 2, 13),
+                    button(text("✖").size(12))
+                        .on_press(Message::RemoveSchedule(index))
+                        .padding(4),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+            );
+        }
+
+        container(
+            column![
+                text("Send a message at a set time, once or repeating. Times are the next time the clock shows them.")
+                    .size(12)
+                    .style(move |_| text::Style {
+                        color: Some(palette.text_muted)
+                    }),
+                controls,
+                list,
+            ]
+            .spacing(8)
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel)),
+            border: Border {
+                radius: 6.0.into(),
+                ..Default::default()
             },
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn editor_line(&self, line: usize) -> Option<String> {
+        let text = self.messages_editor.line(line)?.text.trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Clears the input after a send when that option is on, keeping the
+    /// text until `bots`, the ones that took this send, report back so a
+    /// failure can put it back.
+    fn clear_sent_message(&mut self, bots: HashSet<u64>) {
+        if self.clear_after_send && !bots.is_empty() {
+            self.cleared_message = Some((std::mem::take(&mut self.message), bots));
+        }
+    }
+
+    /// Settles the cleared input once bot `id` reports back: the text goes
+    /// back in if the send didn't go out, and is forgotten once every bot
+    /// sent it.
+    fn settle_cleared_message(&mut self, id: u64, sent: bool) {
+        let Some((_, bots)) = &mut self.cleared_message else {
+            return;
+        };
+        if !bots.remove(&id) {
+            return;
+        }
+
+        if !sent {
+            if let Some((text, _)) = self.cleared_message.take()
+                && self.message.is_empty() {
+                self.message = text;
+            }
+        } else if bots.is_empty() {
+            self.cleared_message = None;
         }
     }
 
-    fn extract_bot_name_from_message(&self, message: &str) -> Option<String> {
-        if let Some(start) = message.find('[') {
-            if let Some(end) = message.find(']') {
-                let bot_part = &message[start+1..end];
-                let bot_name = bot_part.trim_start_matches("🎲 ").trim();
-                return Some(bot_name.to_string());
+    /// Fills in `{channel}`, `{bot}`, `{time}` and `{random:MIN-MAX}` for one
+    /// send. Anything else in braces is left as written.
+    fn render_template(&self, bot: &Bot, channel: &str, raw: &str) -> String {
+        let mut rendered = String::with_capacity(raw.len());
+        let mut rest = raw;
+
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+            let token = &rest[1..end];
+            let value = match token {
+                "channel" => Some(channel.to_string()),
+                "bot" => Some(bot.name.clone()),
+                "time" => Some(chrono::Local::now().format("%H:%M").to_string()),
+                _ => token
+                    .strip_prefix("random:")
+                    .and_then(|range| range.split_once('-'))
+                    .and_then(|(min, max)| Some((min.trim().parse::<u64>().ok()?, max.trim().parse::<u64>().ok()?)))
+                    .filter(|(min, max)| min <= max)
+                    .map(|(min, max)| rand::rng().random_range(min..=max).to_string()),
+            };
+
+            match value {
+                Some(value) => rendered.push_str(&value),
+                None => rendered.push_str(&rest[..=end]),
             }
+            rest = &rest[end + 1..];
         }
-        None
+
+        rendered.push_str(rest);
+        rendered
     }
 
-    fn get_filtered_bots(&self) -> Vec<(usize, &Bot)> {
-        if self.search_query.is_empty() {
-            self.bots.iter().enumerate().collect()
+    /// Runs a rendered message past the blocklist: masks blocked terms, or
+    /// reports the message and gives `None` when blocking.
+    fn screen_message(&mut self, message: String) -> Option<String> {
+        let blocklist = &self.settings.blocklist;
+        if blocklist.is_empty() {
+            return Some(message);
+        }
+        if self.settings.mask_blocked {
+            return Some(mask_blocked(&message, blocklist));
+        }
+
+        match find_blocked(&message, blocklist).map(str::to_string) {
+            Some(term) => {
+                self.chat_history.push(ChatEntry::error(format!("Blocked: contains \"{}\"", term)));
+                None
+            }
+            None => Some(message),
+        }
+    }
+
+    /// Turns a message into the IRC payloads to send: split if needed, and
+    /// with `/me` translated into an ACTION for every part.
+    fn prepare_message(&self, message: &str) -> Vec<String> {
+        let action = action_text(message);
+        let body = action.unwrap_or(message);
+
+        let parts = if self.auto_split {
+            split_message(body, MAX_MESSAGE_LENGTH, self.number_split_parts)
         } else {
-            self.bots
-                .iter()
-                .enumerate()
-                .filter(|(_, bot)| {
-                    bot.name.to_lowercase().contains(&self.search_query.to_lowercase())
+            vec![body.to_string()]
+        };
+
+        if action.is_some() {
+            parts.iter().map(|part| action_payload(part)).collect()
+        } else {
+            parts
+        }
+    }
+
+    fn is_rejected_command(&self, message: &str) -> bool {
+        is_rejected_command(message, self.reject_unknown_commands)
+    }
+
+    /// Reports and blocks a slash command that can't be sent over IRC, when
+    /// the user chose to reject those instead of posting them as text.
+    fn reject_command(&mut self, message: &str) -> bool {
+        if !self.is_rejected_command(message) {
+            return false;
+        }
+
+        if let Some(command) = unsupported_command(message) {
+            self.chat_history.push(ChatEntry::error(format!(
+                "/{} can't be sent over IRC, only /me is supported",
+                command
+            )));
+        }
+        true
+    }
+
+    /// Gates a due random message on the channels being live when the user
+    /// asked for that. Each channel's stream status is cached for a minute;
+    /// stale entries start checks and the next tick acts on the results.
+    /// The message is held only while every channel is held offline.
+    fn send_random_if_live(&mut self, now: Instant) -> Task<Message> {
+        let channels = parse_channels(&self.channel);
+        let stale: Vec<String> = channels
+            .iter()
+            .map(|channel| channel.to_lowercase())
+            .filter(|channel| self.require_live_channels.contains(channel))
+            .filter(|channel| {
+                self.live_status.get(channel).is_none_or(|&(_, checked)| now.duration_since(checked) >= LIVE_STATUS_TTL)
+            })
+            .collect();
+
+        if !stale.is_empty() {
+            let Some(bot) = self.bots.iter().find(|bot| bot.is_available()) else {
+                return Task::done(Message::SendRandomMessage);
+            };
+
+            let token = bot.token.clone();
+            let checks: Vec<Task<Message>> = stale
+                .into_iter()
+                .filter(|channel| self.live_check_pending.insert(channel.clone()))
+                .map(|channel| {
+                    let token = token.clone();
+                    Task::perform(
+                        async move {
+                            let result = helix::is_channel_live(&token, &channel).await;
+                            (channel, result.map_err(|e| e.to_string()))
+                        },
+                        |(channel, result)| Message::LiveStatusChecked(channel, result)
+                    )
                 })
-                .collect()
+                .collect();
+            return Task::batch(checks);
+        }
+
+        if channels.iter().any(|channel| !self.is_held_offline(channel)) {
+            self.live_holding = false;
+            return Task::done(Message::SendRandomMessage);
+        }
+
+        if !self.live_holding {
+            self.live_holding = true;
+            self.chat_history.push(ChatEntry::info(format!("⏸️ {} is offline, holding", self.channel)));
+        }
+        self.schedule_next_message();
+        Task::none()
+    }
+
+    /// Whether random sends skip `channel`: the user asked for it to be
+    /// live and it was last seen offline. An unknown status never holds.
+    fn is_held_offline(&self, channel: &str) -> bool {
+        let channel = channel.to_lowercase();
+        self.require_live_channels.contains(&channel)
+            && self.live_status.get(&channel).is_some_and(|&(live, _)| live == Some(false))
+    }
+
+    fn current_preset(&self) -> SendPreset {
+        SendPreset {
+            all_bots_mode: self.all_bots_mode,
+            simultaneous_mode: self.simultaneous_mode,
+            multiple_bots_mode: self.multiple_bots_mode,
+            multiple_bots_count: self.multiple_bots_count,
+            min_interval: self.min_interval,
+            max_interval: self.max_interval,
+            min_bot_delay: self.min_bot_delay,
+            max_bot_delay: self.max_bot_delay,
+            clear_after_send: self.clear_after_send,
+            auto_split: self.auto_split,
+            number_split_parts: self.number_split_parts,
         }
     }
 
+    fn apply_preset(&mut self, preset: SendPreset) {
+        self.all_bots_mode = preset.all_bots_mode;
+        self.simultaneous_mode = preset.simultaneous_mode;
+        self.multiple_bots_mode = preset.multiple_bots_mode && !preset.all_bots_mode;
+        self.multiple_bots_count = preset.multiple_bots_count.max(1);
+        // A hand-edited preset can have a range backwards, which would make
+        // picking a random value in it panic, so such ranges are swapped.
+        (self.min_interval, self.max_interval) = ordered(preset.min_interval, preset.max_interval);
+        (self.min_bot_delay, self.max_bot_delay) = ordered(preset.min_bot_delay, preset.max_bot_delay);
+        self.clear_after_send = preset.clear_after_send;
+        self.auto_split = preset.auto_split;
+        self.number_split_parts = preset.number_split_parts;
+        self.reschedule_next_message();
+    }
+
     fn schedule_next_message(&mut self) {
         let mut rng = rand::rng();
         let interval = rng.random_range(self.min_interval..=self.max_interval);
         self.next_message_time = Some(Instant::now() + Duration::from_secs(interval));
     }
 
+    /// Applies an interval edit to the pending random message instead of
+    /// the one after it. The new time is at least a second out so a
+    /// half-typed value doesn't fire a send straight away.
+    fn reschedule_next_message(&mut self) {
+        if !self.random_messages_enabled
+            || self.next_message_time.is_none()
+            || self.min_interval > self.max_interval {
+            return;
+        }
+
+        let mut rng = rand::rng();
+        let interval = rng.random_range(self.min_interval..=self.max_interval).max(1);
+        self.next_message_time = Some(Instant::now() + Duration::from_secs(interval));
+    }
+
     pub fn view(&self) -> Element<Message> {
-        if let Some(bot_index) = self.viewing_bot_chat {
+        let palette = self.settings.theme.palette();
+        if let Some(bot_index) = self.viewing_bot_chat.and_then(|id| self.bot_index(id)) {
             return self.view_bot_chat(bot_index);
         }
 
+        if self.show_token_tester {
+            return self.view_token_tester();
+        }
+
         let header = container(
-            text("NGS Chat Bot Utils")
-                .size(24)
+            row![
+                container(
+                    text("NGS Chat Bot Utils")
+                        .size(24)
+                )
+                .width(Length::Fill)
+                .center_x(Length::Fill),
+                button(text("🛑 Emergency Stop"))
+                    .on_press(Message::StopAll)
+                    .style(button::danger)
+                    .padding(8),
+                button(text(match self.settings.theme {
+                    Theme::Dark => "☀️ Light",
+                    Theme::Light => "🌙 Dark",
+                }))
+                .on_press(Message::ToggleTheme)
+                .padding(8),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
         )
         .padding(20)
         .width(Length::Fill)
-        .center_x(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.15, 0.15, 0.2))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.header)),
             ..Default::default()
         });
 
@@ -803,19 +3858,82 @@ impl App {
                         .on_press(Message::LoadConfigPress)
                         .padding(10),
                     button(text("⚙️ Check Bots"))
-                        .on_press(Message::CheckBotsPress)
+                        .on_press_maybe((self.pending_checks == 0).then_some(Message::CheckBotsPress))
+                        .padding(10),
+                    text(
+                        if self.pending_checks > 0 {
+                            format!(
+                                "Checked {}/{}",
+                                self.bots.iter().filter(|bot| bot.status != BotStatus::Checking).count(),
+                                self.bots.len()
+                            )
+                        } else {
+                            "Checks at once:".to_string()
+                        }
+                    )
+                    .size(14),
+                    text_input("", &self.settings.max_concurrent_checks.to_string())
+                        .on_input(Message::MaxConcurrentChecksUpdated)
+                        .on_submit(Message::SaveSettings)
+                        .width(Length::Fixed(50.0))
+                        .padding(10),
+                    button(text("🧹 Remove Dead"))
+                        .on_press_maybe(
+                            (self.bots_checked && self.bots.iter().any(|bot| bot.status == BotStatus::Unavailable))
+                                .then_some(Message::RemoveDeadBots)
+                        )
                         .padding(10),
                     button(text("💌 Load Messages"))
                         .on_press(Message::LoadMessagesPress)
                         .padding(10),
-                    text(format!("Loaded: {}", self.messages.len()))
-                        .size(14),
+                    button(text("💾 Save Messages"))
+                        .on_press(Message::SaveMessagesPress)
+                        .padding(10),
+                    text(
+                        if self.loading_messages {
+                            "⏳ Loading messages...".to_string()
+                        } else {
+                            format!("Loaded: {}", self.messages.len())
+                        }
+                    )
+                    .size(14),
+                    button(text("↩ Undo Send"))
+                        .on_press_maybe((!self.last_action.entries.is_empty()).then_some(Message::UndoLastSend))
+                        .padding(10),
                     button(text("🗑️ Clear Global Chat"))
                         .on_press(Message::ClearGlobalHistory)
                         .padding(10),
+                    button(text("📤 Export History"))
+                        .on_press(Message::ExportHistoryPress)
+                        .padding(10),
                     button(text("🗑️ Clear All"))
                         .on_press(Message::ClearAllHistory)
                         .padding(10),
+                    button(text("⌨️ Hotkeys"))
+                        .on_press(Message::ToggleHotkeySettings)
+                        .padding(10),
+                    button(text("🔑 Test Token"))
+                        .on_press(Message::ToggleTokenTester)
+                        .padding(10),
+                    button(text("🎬 Sequence"))
+                        .on_press(Message::ToggleSequenceEditor)
+                        .padding(10),
+                    button(text("⏰ Schedule"))
+                        .on_press(Message::ToggleSchedule)
+                        .padding(10),
+                    button(text("📢 Multi-Channel"))
+                        .on_press(Message::ToggleChannelMatrix)
+                        .padding(10),
+                    button(text("📊 Stats"))
+                        .on_press(Message::ToggleStats)
+                        .padding(10),
+                    button(text("🛠 Advanced"))
+                        .on_press(Message::ToggleAdvanced)
+                        .padding(10),
+                    checkbox(self.show_summary_on_exit)
+                        .on_toggle(Message::ToggleShowSummaryOnExit),
+                    text("Show summary on exit")
+                        .size(14),
                 ]
                 .spacing(10)
                 .align_y(Alignment::Center),
@@ -823,8 +3941,51 @@ impl App {
                     text_input("Channel Name", &self.channel)
                         .on_input(Message::ChannelNameUpdated)
                         .padding(10),
+                    text("Panels:").size(14),
+                    checkbox(self.settings.layout.show_bots)
+                        .on_toggle(Message::ToggleShowBots),
+                    text("Bots").size(14),
+                    checkbox(self.settings.layout.show_editor)
+                        .on_toggle(Message::ToggleShowEditor),
+                    text("Editor").size(14),
+                    checkbox(self.settings.layout.show_chat)
+                        .on_toggle(Message::ToggleShowChat),
+                    text("Chat").size(14),
+                    checkbox(self.normalize_logins)
+                        .on_toggle(Message::ToggleNormalizeLogins),
+                    text("Lowercase logins on load").size(14),
+                    checkbox(self.settings.use_tls)
+                        .on_toggle(Message::ToggleTls),
+                    text("🔒 TLS (port 6697)").size(14),
                 ]
-                .spacing(10),
+                .spacing(10)
+                .align_y(Alignment::Center),
+                self.hotkey_settings(),
+                self.sequence_settings(),
+                self.schedule_settings(),
+                self.channel_matrix_settings(),
+                self.stats_panel(),
+                self.advanced_settings(),
+                row![
+                    checkbox(self.control_api_enabled)
+                        .on_toggle(Message::ToggleControlApi),
+                    text("Local control API on 127.0.0.1:").size(14),
+                    text_input("Port", &self.control_api_port_input)
+                        .on_input(Message::ControlApiPortUpdated)
+                        .on_submit(Message::ApplyControlApiPort)
+                        .padding(5)
+                        .width(Length::Fixed(80.0)),
+                    text(format!("Token: {}", self.control_api_token))
+                        .size(12)
+                        .style(move |_| text::Style {
+                            color: Some(palette.text_muted)
+                        }),
+                    button(text("🔄 New Token").size(12))
+                        .on_press(Message::RegenerateControlApiToken)
+                        .padding(5),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
 
                 container(
                     column![
@@ -833,10 +3994,25 @@ impl App {
                                 .on_toggle(Message::ToggleRandomMessages),
                             text("Random Messages")
                                 .size(14),
+                            tooltip(
+                                row![
+                                    checkbox(self.no_repeats)
+                                        .on_toggle(Message::ToggleNoRepeats),
+                                    text("No repeats").size(14),
+                                ]
+                                .spacing(5)
+                                .align_y(Alignment::Center),
+                                container(text("Use every message once before any comes up again").size(12))
+                                    .padding(6)
+                                    .style(container::rounded_box),
+                                tooltip::Position::Bottom
+                            ),
                             text(format!(
                                 "Status: {}",
                                 if self.random_messages_enabled { 
-                                    if let Some(next) = self.next_message_time {
+                                    if self.live_holding {
+                                        "⏸️ Channel offline, holding".to_string()
+                                    } else if let Some(next) = self.next_message_time {
                                         let remaining = next.saturating_duration_since(Instant::now()).as_secs();
                                         format!("✅ Active (next in {} sec)", remaining)
                                     } else {
@@ -851,7 +4027,7 @@ impl App {
                                 .on_press_maybe(
                                     if !self.messages.is_empty() 
                                         && !self.channel.is_empty() 
-                                        && self.bots.iter().any(|b| b.available && b.enable) {
+                                        && self.bots.iter().any(|b| b.is_available() && b.enable) {
                                         Some(Message::SendRandomMessageNow)
                                     } else {
                                         None
@@ -861,6 +4037,21 @@ impl App {
                         ]
                         .spacing(15)
                         .align_y(Alignment::Center),
+                        row![
+                            checkbox(
+                                !self.channel.is_empty()
+                                    && parse_channels(&self.channel)
+                                        .iter()
+                                        .all(|channel| self.require_live_channels.contains(&channel.to_lowercase()))
+                            )
+                                .on_toggle_maybe(
+                                    (!self.channel.is_empty()).then_some(Message::ToggleRequireLive)
+                                ),
+                            text("Only while this channel is live")
+                                .size(14),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
                         row![
                             text("Interval (sec):").size(14),
                             text("From:").size(14),
@@ -873,6 +4064,67 @@ impl App {
                                 .on_input(Message::MaxIntervalUpdated)
                                 .padding(5)
                                 .width(Length::Fixed(80.0)),
+                            text("Bot cooldown (sec):").size(14),
+                            text_input("", &self.bot_cooldown.to_string())
+                                .on_input(Message::BotCooldownUpdated)
+                                .padding(5)
+                                .width(Length::Fixed(80.0)),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                        row![
+                            checkbox(self.burst_limiter.enabled)
+                                .on_toggle(Message::ToggleBurstLimit),
+                            text("Global limit:").size(14),
+                            text_input("", &self.burst_limiter.max_messages.to_string())
+                                .on_input(Message::BurstMaxMessagesUpdated)
+                                .padding(5)
+                                .width(Length::Fixed(80.0)),
+                            text("messages per").size(14),
+                            text_input("", &self.burst_limiter.window.as_secs().to_string())
+                                .on_input(Message::BurstWindowUpdated)
+                                .padding(5)
+                                .width(Length::Fixed(80.0)),
+                            text("sec").size(14),
+                            text("Retries:").size(14),
+                            text_input("", &self.max_send_retries.to_string())
+                                .on_input(Message::MaxRetriesUpdated)
+                                .padding(5)
+                                .width(Length::Fixed(60.0)),
+                            text(
+                                if self.burst_holding {
+                                    "⚠️ Global limit reached, holding"
+                                } else {
+                                    ""
+                                }
+                            )
+                            .size(14)
+                            .style(move |_| text::Style {
+                                color: Some(palette.caution)
+                            }),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                        row![
+                            text("Preset:").size(14),
+                            pick_list(
+                                self.presets.keys().cloned().collect::<Vec<_>>(),
+                                self.active_preset.clone(),
+                                Message::ApplyPreset
+                            )
+                            .placeholder("Choose preset")
+                            .padding(5),
+                            text_input("Preset name", &self.preset_name)
+                                .on_input(Message::PresetNameUpdated)
+                                .on_submit(Message::SavePreset(self.preset_name.clone()))
+                                .padding(5)
+                                .width(Length::Fixed(160.0)),
+                            button(text("💾 Save Preset").size(12))
+                                .on_press_maybe(
+                                    (!self.preset_name.trim().is_empty())
+                                        .then(|| Message::SavePreset(self.preset_name.clone()))
+                                )
+                                .padding(5),
                         ]
                         .spacing(10)
                         .align_y(Alignment::Center),
@@ -971,8 +4223,8 @@ impl App {
                 )
                 .padding(10)
                 .width(Length::Fill)
-                .style(|_| container::Style {
-                    background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.22))),
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(palette.panel)),
                     border: Border {
                         radius: 6.0.into(),
                         ..Default::default()
@@ -984,8 +4236,8 @@ impl App {
         )
         .padding(15)
         .width(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.25))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.panel_alt)),
             ..Default::default()
         });
 
@@ -998,6 +4250,32 @@ impl App {
                     .padding(10)
                     .width(Length::Fill)
             );
+
+            let has_bots = !self.bots.is_empty();
+            bot_column = bot_column.push(
+                row![
+                    button(text("Enable All").size(12))
+                        .on_press_maybe(has_bots.then_some(Message::SetAllBotsEnabled(true)))
+                        .padding(5),
+                    button(text("Disable All").size(12))
+                        .on_press_maybe(has_bots.then_some(Message::SetAllBotsEnabled(false)))
+                        .padding(5),
+                    button(text("Invert").size(12))
+                        .on_press_maybe(has_bots.then_some(Message::InvertBotsEnabled))
+                        .padding(5),
+                    text(format!(
+                        "Enabled: {}/{}",
+                        self.bots.iter().filter(|bot| bot.enable).count(),
+                        self.bots.len()
+                    ))
+                    .size(12),
+                    pick_list(SortKey::ALL, self.bot_sort, Message::SortBots)
+                        .placeholder("Sort by...")
+                        .text_size(12),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center)
+            );
             
             let filtered_bots = self.get_filtered_bots();
             
@@ -1015,14 +4293,16 @@ impl App {
                     .padding(20)
                     .width(Length::Fill)
                     .center_x(Length::Fill)
-                    .style(|_| container::Style {
-                        text_color: Some(Color::from_rgb(0.5, 0.5, 0.5)),
+                    .style(move |_| container::Style {
+                        text_color: Some(palette.text_faint),
                         ..Default::default()
                     })
                 );
             } else {
-                for &(index, bot) in &filtered_bots {
-                    bot_column = bot_column.push(self.get_bot_panel(bot.clone(), index));
+                // The preview is the same for every bot, so build it once per frame.
+                let preview_parts = self.preview_parts();
+                for &(_, bot) in &filtered_bots {
+                    bot_column = bot_column.push(self.get_bot_panel(bot, &preview_parts));
                 }
             }
 
@@ -1033,8 +4313,8 @@ impl App {
                             text("Bots").size(16),
                             text(format!("({}/{})", filtered_bots.len(), self.bots.len()))
                                 .size(14)
-                                .style(|_| text::Style {
-                                    color: Some(Color::from_rgb(0.6, 0.6, 0.6))
+                                .style(move |_| text::Style {
+                                    color: Some(palette.text_muted)
                                 })
                         ]
                         .spacing(10)
@@ -1042,8 +4322,8 @@ impl App {
                     )
                     .padding(10)
                     .width(Length::Fill)
-                    .style(|_| container::Style {
-                        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.22))),
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(palette.panel)),
                         ..Default::default()
                     }),
                     scrollable(bot_column)
@@ -1052,8 +4332,8 @@ impl App {
             )
             .width(Length::FillPortion(2))
             .height(Length::Fill)
-            .style(|_| container::Style {
-                background: Some(Background::Color(Color::from_rgb(0.22, 0.22, 0.26))),
+            .style(move |_| container::Style {
+                background: Some(Background::Color(palette.card)),
                 border: Border {
                     radius: 8.0.into(),
                     ..Default::default()
@@ -1064,13 +4344,41 @@ impl App {
 
         let messages_editor_widget = container(
             column![
-                container(text("Message Editor").size(16))
-                    .padding(10)
-                    .width(Length::Fill)
-                    .style(|_| container::Style {
-                        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.22))),
-                        ..Default::default()
-                    }),
+                container(
+                    text(
+                        if self.editor_truncated {
+                            format!(
+                                "Message Editor (showing first {} of {} lines, editing disabled)",
+                                format_count(EDITOR_LINE_LIMIT),
+                                format_count(self.messages.len())
+                            )
+                        } else {
+                            "Message Editor".to_string()
+                        }
+                    )
+                    .size(16)
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(palette.panel)),
+                    ..Default::default()
+                }),
+                row![
+                    checkbox(self.editor_picks_message)
+                        .on_toggle(Message::ToggleEditorLinePicking),
+                    text("Click a line to load it").size(12),
+                    button(text("📤 Send Selected Line").size(12))
+                        .on_press_maybe(
+                            (self.selected_editor_line.is_some() && !self.channel.is_empty())
+                                .then_some(Message::SendSelectedLine)
+                        )
+                        .padding(5),
+                ]
+                .spacing(10)
+                .padding([5, 10])
+                .align_y(Alignment::Center),
+                self.long_lines_warning(),
                 scrollable(
                     text_editor(&self.messages_editor)
                         .on_action(Message::MessagesEditorAction)
@@ -1081,8 +4389,8 @@ impl App {
         )
         .width(Length::FillPortion(2))
         .height(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.22, 0.22, 0.26))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.card)),
             border: Border {
                 radius: 8.0.into(),
                 ..Default::default()
@@ -1092,26 +4400,50 @@ impl App {
 
         let chat_area = {
             let mut message_column = column![].spacing(5);
+            let query = self.chat_search_query.trim();
+            let shown: Vec<&ChatEntry> = self.chat_history
+                .iter()
+                .filter(|entry| query.is_empty() || entry.matches(query))
+                .collect();
             
-            if self.chat_history.is_empty() {
+            if shown.is_empty() {
+                let note = if self.chat_history.is_empty() { "No messages yet" } else { "No messages match" };
                 message_column = message_column.push(
-                    container(text("No messages yet").size(14))
+                    container(text(note).size(14))
                         .padding(20)
                         .width(Length::Fill)
                         .center_x(Length::Fill)
-                        .style(|_| container::Style {
-                            text_color: Some(Color::from_rgb(0.5, 0.5, 0.5)),
+                        .style(move |_| container::Style {
+                            text_color: Some(palette.text_faint),
                             ..Default::default()
                         })
                 );
             } else {
-                for (idx, msg) in self.chat_history.iter().enumerate() {
+                // Only rows near the viewport are built; spacers stand in for
+                // the rest so the scrollbar keeps its full length.
+                let visible = self.visible_chat_rows(shown.len());
+                let row_stride = CHAT_ROW_HEIGHT + 5.0;
+                if visible.start > 0 {
+                    message_column = message_column.push(
+                        Space::new().height(visible.start as f32 * row_stride - 5.0)
+                    );
+                }
+                let hidden_below = shown.len() - visible.end;
+
+                for position in visible {
+                    let msg = shown[position];
+                    // Runs from one bot name it once; the rest indent under it.
+                    let grouped = position > 0 && msg.continues(shown[position - 1]);
+                    let line = if grouped { msg.without_bot() } else { msg.to_string() };
+                    let background = entry_background(&palette, msg.kind);
                     let message_button = button(
-                        container(text(msg).size(14))
-                            .padding(8)
+                        container(with_full_text_tooltip(&line, CHAT_PREVIEW_MAX_WIDTH, 14))
+                            .padding(Padding::new(8.0).left(if grouped { 32.0 } else { 8.0 }))
                             .width(Length::Fill)
-                            .style(|_| container::Style {
-                                background: Some(Background::Color(Color::from_rgb(0.25, 0.25, 0.3))),
+                            .height(Length::Fixed(CHAT_ROW_HEIGHT))
+                            .clip(true)
+                            .style(move |_| container::Style {
+                                background: Some(Background::Color(background)),
                                 border: Border {
                                     radius: 4.0.into(),
                                     ..Default::default()
@@ -1119,15 +4451,32 @@ impl App {
                                 ..Default::default()
                             })
                     )
-                    .on_press(Message::MessageClicked(idx))
+                    .on_press(Message::MessageClicked(msg.id))
                     .style(|_, _| button::Style {
                         background: None,
                         border: Border::default(),
                         ..Default::default()
                     })
                     .padding(0);
-                    
-                    message_column = message_column.push(message_button);
+
+                    let copy_button = button(text("📋").size(12))
+                        .on_press(Message::CopyChatEntry(msg.id))
+                        .padding(6);
+                    let delete_button = button(text("×").size(12))
+                        .on_press(Message::DeleteHistoryEntry(msg.id))
+                        .padding(6);
+
+                    message_column = message_column.push(
+                        row![message_button, copy_button, delete_button]
+                            .spacing(5)
+                            .align_y(Alignment::Center)
+                    );
+                }
+
+                if hidden_below > 0 {
+                    message_column = message_column.push(
+                        Space::new().height(hidden_below as f32 * row_stride - 5.0)
+                    );
                 }
             }
 
@@ -1136,23 +4485,30 @@ impl App {
                     container(
                         row![
                             text("Global Chat").size(16),
-                            text("(Click on a message to open bot chat)")
+                            text("(Click on a message to open bot chat, 📋 to copy it, × to delete it)")
                                 .size(11)
-                                .style(|_| text::Style {
-                                    color: Some(Color::from_rgb(0.5, 0.5, 0.5))
-                                })
+                                .style(move |_| text::Style {
+                                    color: Some(palette.text_faint)
+                                }),
+                            self.chat_legend(),
+                            text_input("🔍 Search chat by bot or text...", &self.chat_search_query)
+                                .on_input(Message::ChatSearchUpdated)
+                                .size(12)
+                                .padding(5)
+                                .width(Length::Fixed(220.0))
                         ]
                         .spacing(10)
                         .align_y(Alignment::Center)
                     )
                     .padding(10)
                     .width(Length::Fill)
-                    .style(|_| container::Style {
-                        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.22))),
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(palette.panel)),
                         ..Default::default()
                     }),
                     container(
                         scrollable(message_column)
+                            .on_scroll(Message::ChatScrolled)
                             .height(Length::Fill)
                     )
                     .padding(10)
@@ -1163,20 +4519,29 @@ impl App {
                                 text_input("Enter message...", &self.message)
                                     .on_input(Message::MessageUpdated)
                                     .on_submit_maybe(
-                                        if !self.message.is_empty() && self.bots.iter().any(|b| b.available && b.enable) {
-                                            self.bots.iter()
-                                                .position(|b| b.available && b.enable)
-                                                .map(Message::SendMessage)
+                                        if !self.message.is_empty() && !self.blocks_send(&self.message) && self.pick_single_bot().is_some() {
+                                            Some(Message::SendMessageSingle)
                                         } else {
                                             None
                                         }
                                     )
                                     .padding(10),
+                                self.length_counter(&self.message),
+                                tooltip(
+                                    button(text("📌"))
+                                        .on_press(Message::SaveMessageAsDefault)
+                                        .padding(10),
+                                    container(text("Use this message on startup (empty clears it)").size(12))
+                                        .padding(6)
+                                        .style(container::rounded_box),
+                                    tooltip::Position::Bottom
+                                ),
                                 button(text("🎲 Random"))
                                     .on_press_maybe(
                                         if !self.message.is_empty() 
+                                            && !self.blocks_send(&self.message)
                                             && !self.channel.is_empty() 
-                                            && self.bots.iter().any(|b| b.available && b.enable) {
+                                            && self.bots.iter().any(|b| b.is_available() && b.enable) {
                                             Some(Message::SendMessageRandomBot)
                                         } else {
                                             None
@@ -1186,8 +4551,9 @@ impl App {
                                 button(text("👥 All Bots"))
                                     .on_press_maybe(
                                         if !self.message.is_empty() 
+                                            && !self.blocks_send(&self.message)
                                             && !self.channel.is_empty() 
-                                            && self.bots.iter().any(|b| b.available && b.enable) {
+                                            && self.bots.iter().any(|b| b.is_available() && b.enable) {
                                             Some(Message::SendMessageAllBots)
                                         } else {
                                             None
@@ -1195,12 +4561,54 @@ impl App {
                                     )
                                     .padding(10),
                             ]
-                            .spacing(10),
+                            .spacing(10)
+                            .align_y(Alignment::Center),
                             row![
                                 checkbox(self.clear_after_send)
                                     .on_toggle(Message::ToggleClearAfterSend),
                                 text("Clear after send")
                                     .size(14),
+                                checkbox(self.auto_split)
+                                    .on_toggle(Message::ToggleAutoSplit),
+                                text("Split long messages")
+                                    .size(14),
+                                checkbox(self.number_split_parts)
+                                    .on_toggle_maybe(
+                                        if self.auto_split {
+                                            Some(Message::ToggleNumberSplitParts)
+                                        } else {
+                                            None
+                                        }
+                                    ),
+                                text("Number parts (1/3)")
+                                    .size(14),
+                                checkbox(self.reject_unknown_commands)
+                                    .on_toggle(Message::ToggleRejectUnknownCommands),
+                                text("Block commands other than /me")
+                                    .size(14),
+                                button(text("🚫 Load Blocklist").size(14))
+                                    .on_press(Message::LoadBlocklistPress)
+                                    .padding(5),
+                                checkbox(self.settings.mask_blocked)
+                                    .on_toggle(Message::ToggleMaskBlocked),
+                                text(format!("Mask blocked terms ({})", self.settings.blocklist.len()))
+                                    .size(14),
+                                checkbox(self.settings.confirm_mass_sends)
+                                    .on_toggle(Message::ToggleConfirmMassSends),
+                                text("Confirm mass sends")
+                                    .size(14),
+                            ]
+                            .spacing(10)
+                            .align_y(Alignment::Center),
+                            row![
+                                text("Enter sends with:")
+                                    .size(14),
+                                pick_list(
+                                    SingleSendPolicy::ALL,
+                                    Some(self.single_send_policy),
+                                    Message::SingleSendPolicyChanged
+                                )
+                                .text_size(14),
                             ]
                             .spacing(10)
                             .align_y(Alignment::Center)
@@ -1213,8 +4621,8 @@ impl App {
             )
             .width(Length::FillPortion(3))
             .height(Length::Fill)
-            .style(|_| container::Style {
-                background: Some(Background::Color(Color::from_rgb(0.22, 0.22, 0.26))),
+            .style(move |_| container::Style {
+                background: Some(Background::Color(palette.card)),
                 border: Border {
                     radius: 8.0.into(),
                     ..Default::default()
@@ -1223,17 +4631,21 @@ impl App {
             })
         };
 
+        let mut panels = row![].spacing(15);
+        if self.settings.layout.show_bots {
+            panels = panels.push(bot_list);
+        }
+        if self.settings.layout.show_editor {
+            panels = panels.push(messages_editor_widget);
+        }
+        if self.settings.layout.show_chat {
+            panels = panels.push(chat_area);
+        }
+
         let body = container(
             column![
                 controls,
-                container(
-                    row![
-                        bot_list,
-                        messages_editor_widget,
-                        chat_area,
-                    ]
-                    .spacing(15)
-                )
+                container(panels)
                 .padding(15)
                 .height(Length::Fill)
             ]
@@ -1247,15 +4659,119 @@ impl App {
         )
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.12, 0.12, 0.15))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.background)),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn view_token_tester(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        let header = container(
+            row![
+                button(text("← Back"))
+                    .on_press(Message::ToggleTokenTester)
+                    .padding(10),
+                text("Test Token").size(24),
+            ]
+            .spacing(20)
+            .align_y(Alignment::Center)
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.header)),
+            ..Default::default()
+        });
+
+        let can_test = !self.tester_token.trim().is_empty() && !self.tester_running;
+        let input = row![
+            text_input("Paste a token (without oauth:)", &self.tester_token)
+                .on_input(Message::TesterTokenUpdated)
+                .on_submit_maybe(can_test.then_some(Message::TestToken))
+                .secure(true)
+                .padding(10),
+            button(text(if self.tester_running { "⏳ Testing..." } else { "🔍 Test" }))
+                .on_press_maybe(can_test.then_some(Message::TestToken))
+                .padding(10),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let result: Element<Message> = match &self.tester_result {
+            None => text("The token is only checked, it is not added to the bot list.")
+                .size(14)
+                .style(move |_| text::Style {
+                    color: Some(palette.text_muted)
+                })
+                .into(),
+            Some(Err(error)) => text(format!("❌ {}", error))
+                .size(14)
+                .style(move |_| text::Style {
+                    color: Some(palette.error_text)
+                })
+                .into(),
+            Some(Ok(report)) => {
+                let expires = if report.info.expires_in == 0 {
+                    "never".to_string()
+                } else {
+                    format!(
+                        "in {}h {}m",
+                        report.info.expires_in / 3600,
+                        report.info.expires_in / 60 % 60
+                    )
+                };
+                let scopes = if report.info.scopes.is_empty() {
+                    "-".to_string()
+                } else {
+                    report.info.scopes.join(", ")
+                };
+                let missing = report.info.missing_chat_scopes();
+                let missing_scopes = if missing.is_empty() {
+                    "Chat scopes: ✅ OK".to_string()
+                } else {
+                    format!("Chat scopes: ⚠️ missing {}", missing.join(", "))
+                };
+
+                column![
+                    text(format!("Login: {}", report.info.login)).size(14),
+                    text(format!("Scopes: {}", scopes)).size(14),
+                    text(missing_scopes).size(14),
+                    text(format!("Expires: {}", expires)).size(14),
+                    text(if report.irc_ok { "IRC login: ✅ OK" } else { "IRC login: ❌ Failed" }).size(14),
+                    button(text("➕ Add as bot"))
+                        .on_press_maybe(report.irc_ok.then_some(Message::AddTestedBot))
+                        .padding(10),
+                ]
+                .spacing(8)
+                .into()
+            }
+        };
+
+        container(
+            column![
+                header,
+                container(
+                    column![input, result].spacing(20)
+                )
+                .padding(20)
+                .max_width(700),
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.background)),
             ..Default::default()
         })
         .into()
     }
 
     fn view_bot_chat(&self, bot_index: usize) -> Element<Message> {
+        let palette = self.settings.theme.palette();
         let bot = &self.bots[bot_index];
+        let bot_id = bot.id;
         
         let header = container(
             row![
@@ -1265,16 +4781,44 @@ impl App {
                 text(format!("Bot Chat: {}", bot.name))
                     .size(24),
                 button(text("🗑️ Clear History"))
-                    .on_press(Message::ClearBotHistory(bot_index))
+                    .on_press(Message::ClearBotHistory(bot_id))
+                    .padding(10),
+                button(text("📤 Export History"))
+                    .on_press(Message::ExportBotHistory(bot_id))
                     .padding(10),
+                button(text(format!("✖ Cancel {} Pending", self.pending_count(bot_id))))
+                    .on_press_maybe(
+                        (self.pending_count(bot_id) > 0).then_some(Message::CancelPendingSends(bot_id))
+                    )
+                    .padding(10),
+                text(
+                    if self.revealed_token == Some(bot_id) {
+                        bot.token.clone()
+                    } else {
+                        bot.masked_token()
+                    }
+                )
+                .size(12)
+                .style(move |_| text::Style {
+                    color: Some(palette.text_muted)
+                }),
+                if self.revealed_token == Some(bot_id) {
+                    button(text("🙈 Hide Token").size(12))
+                        .on_press(Message::HideToken)
+                        .padding(5)
+                } else {
+                    button(text("👁 Reveal Token").size(12))
+                        .on_press(Message::RevealToken(bot_id))
+                        .padding(5)
+                },
             ]
             .spacing(20)
             .align_y(Alignment::Center)
         )
         .padding(20)
         .width(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.15, 0.15, 0.2))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.header)),
             ..Default::default()
         });
 
@@ -1286,19 +4830,19 @@ impl App {
                     .padding(20)
                     .width(Length::Fill)
                     .center_x(Length::Fill)
-                    .style(|_| container::Style {
-                        text_color: Some(Color::from_rgb(0.5, 0.5, 0.5)),
+                    .style(move |_| container::Style {
+                        text_color: Some(palette.text_faint),
                         ..Default::default()
                     })
             );
         } else {
-            for msg in &bot.chat_history {
+            for msg in bot.chat_history.iter() {
                 message_column = message_column.push(
-                    container(text(msg).size(14))
+                    container(text(msg.to_string()).size(14))
                         .padding(8)
                         .width(Length::Fill)
-                        .style(|_| container::Style {
-                            background: Some(Background::Color(Color::from_rgb(0.25, 0.25, 0.3))),
+                        .style(move |_| container::Style {
+                            background: Some(Background::Color(palette.bubble)),
                             border: Border {
                                 radius: 4.0.into(),
                                 ..Default::default()
@@ -1322,19 +4866,23 @@ impl App {
                         text_input("Enter message...", &self.bot_message_input)
                             .on_input(Message::BotMessageUpdated)
                             .on_submit_maybe(
-                                if !self.bot_message_input.is_empty() && bot.available && bot.enable {
-                                    Some(Message::SendBotMessage(bot_index))
+                                if !self.bot_message_input.is_empty()
+                                    && !self.blocks_send(&self.bot_message_input)
+                                    && bot.is_available() && bot.enable {
+                                    Some(Message::SendBotMessage(bot_id))
                                 } else {
                                     None
                                 }
                             )
                             .padding(10),
+                        self.length_counter(&self.bot_message_input),
                         button(text("📤 Send"))
                             .on_press_maybe(
                                 if !self.bot_message_input.is_empty() 
+                                    && !self.blocks_send(&self.bot_message_input)
                                     && !self.channel.is_empty() 
-                                    && bot.available && bot.enable {
-                                    Some(Message::SendBotMessage(bot_index))
+                                    && bot.is_available() && bot.enable {
+                                    Some(Message::SendBotMessage(bot_id))
                                 } else {
                                     None
                                 }
@@ -1342,6 +4890,45 @@ impl App {
                             .padding(10),
                     ]
                     .spacing(10)
+                    .align_y(Alignment::Center)
+                )
+                .padding(10)
+                .width(Length::Fill),
+                container(
+                    row![
+                        text_input(
+                            &format!("Test channel (default: {})", bot.login),
+                            &self.settings.test_channel
+                        )
+                        .on_input(Message::TestChannelUpdated)
+                        .on_submit(Message::SaveSettings)
+                        .padding(10),
+                        button(text("🧪 Send to Test Channel"))
+                            .on_press_maybe(
+                                (!self.bot_message_input.is_empty() && bot.is_available() && bot.enable)
+                                    .then_some(Message::SendToTestChannel(bot_id))
+                            )
+                            .padding(10),
+                    ]
+                    .spacing(10)
+                )
+                .padding([0, 10])
+                .width(Length::Fill),
+                container(
+                    row![
+                        text_input("Whisper to (username)", &self.whisper_target)
+                            .on_input(Message::WhisperTargetUpdated)
+                            .padding(10),
+                        button(text("✉️ Whisper"))
+                            .on_press_maybe(
+                                (!self.bot_message_input.is_empty()
+                                    && !self.whisper_target.trim().is_empty()
+                                    && bot.enable)
+                                    .then_some(Message::SendWhisper(bot_id))
+                            )
+                            .padding(10),
+                    ]
+                    .spacing(10)
                 )
                 .padding(10)
                 .width(Length::Fill)
@@ -1349,8 +4936,8 @@ impl App {
         )
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.22, 0.22, 0.26))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.card)),
             border: Border {
                 radius: 8.0.into(),
                 ..Default::default()
@@ -1369,46 +4956,316 @@ impl App {
         )
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.12, 0.12, 0.15))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.background)),
             ..Default::default()
         })
         .into()
     }
 
+    pub fn theme(&self) -> iced::Theme {
+        self.settings.theme.iced()
+    }
+
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        if self.random_messages_enabled {
+        let ticks = if self.random_messages_enabled || !self.schedules.is_empty() {
             iced::time::every(Duration::from_millis(1000))
                 .map(Message::Tick)
         } else {
             Subscription::none()
+        };
+
+        // Bots only have a connection to hear mentions on once they sent.
+        let mentions = if self.bots.iter().any(|bot| bot.last_sent.is_some()) {
+            iced::time::every(MENTION_POLL).map(|_| Message::PollMentions)
+        } else {
+            Subscription::none()
+        };
+
+        let hotkeys = iced::keyboard::listen()
+            .filter_map(|event| Hotkey::from_event(event).map(Message::HotkeyPressed));
+
+        let close_requests = iced::window::close_requests()
+            .map(Message::WindowCloseRequested);
+
+        let control_api = if self.control_api_enabled {
+            Subscription::run_with(
+                (self.control_api_port, self.control_api_token.clone()),
+                control_api::listen
+            )
+            .map(Message::ControlApi)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([ticks, mentions, hotkeys, close_requests, control_api])
+    }
+
+    fn hotkey_settings(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if !self.show_hotkey_settings {
+            return column![].into();
+        }
+
+        let mut rows = column![
+            text("Hotkeys work while the window is focused and no input has focus. Press Escape to cancel rebinding.")
+                .size(12)
+                .style(move |_| text::Style {
+                    color: Some(palette.text_muted)
+                })
+        ]
+        .spacing(8);
+
+        for action in HotkeyAction::ALL {
+            let binding = if self.rebinding_hotkey == Some(action) {
+                "Press a key...".to_string()
+            } else {
+                self.settings.hotkeys
+                    .get(action)
+                    .map(|hotkey| hotkey.to_string())
+                    .unwrap_or_else(|| "Not set".to_string())
+            };
+
+            rows = rows.push(
+                row![
+                    text(action.label())
+                        .size(14)
+                        .width(Length::Fixed(160.0)),
+                    text(binding)
+                        .size(14)
+                        .width(Length::Fixed(120.0)),
+                    button(text("Rebind").size(12))
+                        .on_press(Message::StartRebindHotkey(action))
+                        .padding(5),
+                    button(text("Clear").size(12))
+                        .on_press(Message::ClearHotkey(action))
+                        .padding(5),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+            );
+        }
+
+        container(rows)
+            .padding(10)
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(palette.panel)),
+                border: Border {
+                    radius: 6.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// Whether `message` can't go out because it's over the length limit
+    /// and won't be split.
+    fn blocks_send(&self, message: &str) -> bool {
+        !self.auto_split && message_too_long(message)
+    }
+
+    /// Live `count/500` next to a message input, red when the message is
+    /// too long to send and amber when it will be split.
+    fn length_counter<'a>(&self, message: &str) -> Element<'a, Message> {
+        let palette = self.settings.theme.palette();
+        let count = message.chars().count();
+        let color = if self.blocks_send(message) {
+            palette.danger
+        } else if message_too_long(message) {
+            palette.caution
+        } else {
+            palette.text_faint
+        };
+
+        text(format!("{}/{}", count, MAX_MESSAGE_LENGTH))
+            .size(12)
+            .style(move |_| text::Style {
+                color: Some(color)
+            })
+            .into()
+    }
+
+    /// Lists the loaded messages that are over the length limit.
+    fn long_lines_warning(&self) -> Element<'_, Message> {
+        let palette = self.settings.theme.palette();
+        if self.long_lines.is_empty() {
+            return column![].into();
+        }
+
+        let shown = self.long_lines
+            .iter()
+            .take(10)
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let more = if self.long_lines.len() > 10 {
+            format!(" and {} more", self.long_lines.len() - 10)
+        } else {
+            String::new()
+        };
+        let note = if self.auto_split { "they will be split" } else { "they won't send" };
+
+        container(
+            text(format!(
+                "⚠️ Over {} characters, {}: line {}{}",
+                MAX_MESSAGE_LENGTH, note, shown, more
+            ))
+            .size(12)
+            .style(move |_| text::Style {
+                color: Some(palette.caution)
+            })
+        )
+        .padding([0, 10])
+        .into()
+    }
+
+    fn health_label<'a>(&self, bot: &Bot) -> Element<'a, Message> {
+        let palette = self.settings.theme.palette();
+        let score = bot.health_score(&self.settings.health_weights);
+        let color = if score >= 80 {
+            palette.success
+        } else if score >= 50 {
+            palette.warning
+        } else {
+            palette.danger
+        };
+
+        text(format!("Health: {}", score))
+            .size(11)
+            .style(move |_| text::Style {
+                color: Some(color)
+            })
+            .into()
+    }
+
+    /// Swatches for the chat row colors.
+    fn chat_legend<'a>(&self) -> Element<'a, Message> {
+        let palette = self.settings.theme.palette();
+        let swatch = |label: &'a str, kind: EntryKind| {
+            let color = entry_background(&palette, kind);
+            row![
+                container(text(""))
+                    .width(Length::Fixed(10.0))
+                    .height(Length::Fixed(10.0))
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(color)),
+                        border: Border {
+                            radius: 2.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }),
+                text(label).size(11).style(move |_| text::Style {
+                    color: Some(palette.text_faint)
+                })
+            ]
+            .spacing(4)
+            .align_y(Alignment::Center)
+        };
+
+        row![
+            swatch("Sent", EntryKind::Sent),
+            swatch("Mention", EntryKind::Received),
+            swatch("Notice", EntryKind::Info),
+            swatch("Error", EntryKind::Error),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center)
+        .into()
+    }
+
+    /// The global chat rows in or near view, out of `total`. A few extra
+    /// on each side keep fast scrolling from showing gaps. The offset can
+    /// be left over from a longer list before a search narrowed it, so the
+    /// range is pulled back to end at the last row.
+    fn visible_chat_rows(&self, total: usize) -> std::ops::Range<usize> {
+        let (offset, height) = self.chat_viewport;
+        let row_stride = CHAT_ROW_HEIGHT + 5.0;
+        let count = (height / row_stride).ceil() as usize + 2 * CHAT_OVERSCAN;
+        let first = ((offset / row_stride) as usize)
+            .saturating_sub(CHAT_OVERSCAN)
+            .min(total.saturating_sub(count));
+        first..(first + count).min(total)
+    }
+
+    /// The login round trip from the last check, colored by how slow it was.
+    fn ping_label<'a>(&self, bot: &Bot) -> Element<'a, Message> {
+        let palette = self.settings.theme.palette();
+        let Some(ping) = bot.ping_ms.filter(|_| bot.enable) else {
+            return column![].into();
+        };
+        let color = if ping < 200 {
+            palette.success
+        } else if ping < 800 {
+            palette.warning
+        } else {
+            palette.danger
+        };
+
+        text(format!("{}ms", ping))
+            .size(11)
+            .style(move |_| text::Style {
+                color: Some(color)
+            })
+            .into()
+    }
+
+    /// The parts the current message will be sent as, with actions shown
+    /// back as `/me` for the send preview.
+    fn preview_parts(&self) -> Vec<String> {
+        if self.message.is_empty() {
+            return Vec::new();
         }
+
+        self.prepare_message(&self.message)
+            .into_iter()
+            .map(|part| match part.strip_prefix("\x01ACTION ") {
+                Some(action) => format!("/me {}", action.trim_end_matches('\x01')),
+                None => part,
+            })
+            .collect()
     }
 
-    fn get_bot_panel(&self, bot: Bot, index: usize) -> Element<Message> {
-        let available = bot.available;
+    fn get_bot_panel<'a>(&'a self, bot: &'a Bot, parts: &[String]) -> Element<'a, Message> {
+        let palette = self.settings.theme.palette();
+        let id = bot.id;
+        let available = bot.is_available();
         let enabled = bot.enable;
         
-        let status_color = if !enabled {
-            Color::from_rgb(0.5, 0.5, 0.5)
-        } else if available {
-            Color::from_rgb(0.2, 0.8, 0.3)
-        } else {
-            Color::from_rgb(0.8, 0.2, 0.2)
+        let expires_soon = bot.expires_within(TOKEN_EXPIRY_WARNING);
+        let status_color = match bot.status {
+            _ if !enabled => palette.text_faint,
+            BotStatus::Unknown => palette.text_faint,
+            BotStatus::Checking => palette.text_muted,
+            BotStatus::Available if expires_soon.is_some() => palette.warning,
+            BotStatus::Available => palette.success,
+            BotStatus::Unavailable => palette.danger,
         };
         
-        let status_indicator = text("●")
+        let status_indicator = text(match bot.status {
+            BotStatus::Checking if enabled => "◌",
+            BotStatus::Unknown => "○",
+            _ => "●",
+        })
             .style(move |_| text::Style {
                 color: Some(status_color)
             });
 
         let status_text = text(
-            if !enabled {
-                "Disabled"
-            } else if available {
-                "Available"
-            } else {
-                "Unavailable"
+            match bot.status {
+                _ if !enabled => "Disabled".to_string(),
+                BotStatus::Unknown => "Not checked".to_string(),
+                BotStatus::Checking => "Checking…".to_string(),
+                BotStatus::Available => match expires_soon {
+                    Some(left) => format!("Token expires in {}h {}m", left.as_secs() / 3600, left.as_secs() / 60 % 60),
+                    None => "Available".to_string(),
+                },
+                BotStatus::Unavailable if bot.missing_scopes.iter().any(|scope| scope == helix::SEND_SCOPE) => {
+                    "Missing chat:edit".to_string()
+                }
+                BotStatus::Unavailable => "Unavailable".to_string(),
             }
         )
         .size(12)
@@ -1419,25 +5276,53 @@ impl App {
         let content = container(
             row![
                 column![
-                    text(bot.name.clone()).size(14),
+                    with_full_text_tooltip(&bot.display_name(), BOT_NAME_MAX_WIDTH, 14),
                     row![
                         status_indicator,
-                        status_text
+                        status_text,
+                        self.ping_label(bot)
                     ]
                     .spacing(5)
                     .align_y(Alignment::Center),
-                    text(format!("Messages: {}", bot.chat_history.len()))
+                    self.health_label(bot),
+                    text_input(&format!("#{}", self.channel), bot.channel.as_deref().unwrap_or(""))
+                        .on_input(move |channel| Message::BotChannelUpdated(id, channel))
+                        .size(12)
+                        .padding(4)
+                        .width(Length::Fixed(140.0)),
+                    text(
+                        match self.pending_count(id) {
+                            0 => format!("Messages: {}", bot.chat_history.len()),
+                            pending => format!("Messages: {} · ⏳ {} pending", bot.chat_history.len(), pending),
+                        }
+                    )
                         .size(11)
-                        .style(|_| text::Style {
-                            color: Some(Color::from_rgb(0.6, 0.6, 0.6))
+                        .style(move |_| text::Style {
+                            color: Some(palette.text_muted)
                         })
                 ]
                 .spacing(4),
                 row![
                     checkbox(enabled)
-                        .on_toggle(move |checked| Message::ToggleBotEnabled(index, checked)),
+                        .on_toggle(move |checked| Message::ToggleBotEnabled(id, checked)),
+                    button(text(if self.selected_bot == Some(id) { "★" } else { "☆" }))
+                        .on_press(Message::SelectActiveBot(id))
+                        .padding(5),
+                    tooltip(
+                        button(text(if bot.moderator { "🛡" } else { "👤" }))
+                            .on_press(Message::ToggleBotModerator(id))
+                            .padding(5),
+                        container(text(if bot.moderator {
+                            "Moderator: 100 messages per 30s"
+                        } else {
+                            "Regular user: 20 messages per 30s"
+                        }).size(12))
+                            .padding(6)
+                            .style(container::rounded_box),
+                        tooltip::Position::Bottom
+                    ),
                     button(text("💬"))
-                        .on_press(Message::ToggleBotChatView(index))
+                        .on_press(Message::ToggleBotChatView(id))
                         .padding(5),
                 ]
                 .spacing(5)
@@ -1448,8 +5333,8 @@ impl App {
             .padding(10)
         )
         .width(Length::Fill)
-        .style(|_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.28, 0.28, 0.32))),
+        .style(move |_| container::Style {
+            background: Some(Background::Color(palette.highlight)),
             border: Border {
                 radius: 6.0.into(),
                 ..Default::default()
@@ -1465,10 +5350,202 @@ impl App {
             })
             .padding(0);
 
-        if available && enabled && !self.message.is_empty() {
-            btn.on_press(Message::SendMessage(index)).into()
+        if available && enabled && !parts.is_empty() && !self.blocks_send(&self.message) {
+            let preview = if parts.len() > 1 {
+                format!("{} will send {} messages:\n{}", bot.name, parts.len(), parts.join("\n"))
+            } else {
+                format!("{} will send:\n{}", bot.name, parts.join(""))
+            };
+
+            tooltip(
+                btn.on_press(Message::SendMessage(id)),
+                container(text(preview).size(12))
+                    .padding(6)
+                    .max_width(400)
+                    .style(container::rounded_box),
+                tooltip::Position::Bottom
+            )
+            .into()
         } else {
             btn.into()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Rewrites a token file without the lines whose token is in `tokens`,
+/// leaving every other line, names and blank lines included, as it was.
+fn remove_tokens_from_file(path: &Path, tokens: &HashSet<String>) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            let token = line.split_once('|').map_or(*line, |(token, _)| token).trim();
+            !tokens.contains(token)
+        })
+        .collect();
+
+    let mut output = kept.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    std::fs::write(path, output)?;
+    Ok(())
+}
+
+const MAX_SEND_RETRIES: u32 = 10;
+const LIVE_STATUS_TTL: Duration = Duration::from_secs(60);
+const MENTION_POLL: Duration = Duration::from_millis(500);
+const EDITOR_LINE_LIMIT: usize = 5_000;
+/// Tokens closer than this to expiring show an amber status.
+const TOKEN_EXPIRY_WARNING: Duration = Duration::from_secs(24 * 60 * 60);
+
+const BOT_NAME_MAX_WIDTH: usize = 24;
+const CHAT_PREVIEW_MAX_WIDTH: usize = 200;
+/// Global chat rows are all this tall so the visible ones can be worked
+/// out from the scroll offset.
+const CHAT_ROW_HEIGHT: f32 = 36.0;
+const CHAT_OVERSCAN: usize = 5;
+
+/// Formats a count with thousands separators, e.g. `50,000`.
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+/// The two ends of a range, smaller first.
+fn ordered(a: u64, b: u64) -> (u64, u64) {
+    (a.min(b), a.max(b))
+}
+
+/// 1-based numbers of the lines in `messages` that are over the length
+/// limit.
+fn long_line_numbers(messages: &[String]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| message_too_long(message))
+        .map(|(line, _)| line + 1)
+        .collect()
+}
+
+/// Cuts `value` to at most `max_width` display columns, ending with an
+/// ellipsis when anything was dropped. Wide characters such as CJK take
+/// two columns.
+fn truncate_display(value: &str, max_width: usize) -> Option<String> {
+    let char_width = |c: char| c.width().unwrap_or(0);
+    if value.chars().map(char_width).sum::<usize>() <= max_width {
+        return None;
+    }
+
+    let mut width = 0;
+    let mut truncated = String::new();
+    for c in value.chars() {
+        width += char_width(c);
+        if width + 1 > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('…');
+    Some(truncated)
+}
+
+/// The row color a chat entry of `kind` is shown on.
+fn entry_background(palette: &Palette, kind: EntryKind) -> Color {
+    match kind {
+        EntryKind::Sent | EntryKind::RandomSent => palette.bubble,
+        EntryKind::Received => palette.highlight,
+        EntryKind::Info => palette.bubble_info,
+        EntryKind::Error => palette.bubble_error,
+    }
+}
+
+/// Text that is truncated for layout, with the full value in a tooltip.
+fn with_full_text_tooltip<'a>(value: &str, max_width: usize, size: u32) -> Element<'a, Message> {
+    match truncate_display(value, max_width) {
+        Some(short) => tooltip(
+            text(short).size(size),
+            container(text(value.to_string()).size(12))
+                .padding(6)
+                .max_width(400)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom
+        )
+        .into(),
+        None => text(value.to_string()).size(size).into(),
+    }
+}
+
+fn generate_api_token() -> String {
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivered(ids: &[(&str, &str)]) -> Result<Delivery, String> {
+        Ok(Delivery {
+            retries: 0,
+            latency: Duration::ZERO,
+            message_ids: ids.iter().map(|(channel, id)| (channel.to_string(), id.to_string())).collect(),
+        })
+    }
+
+    #[test]
+    fn one_action_collects_every_send_until_the_next() {
+        let mut action = SendAction::default();
+
+        // Two steps of one sequence run.
+        action.log(7, [1, 2]);
+        action.track(7, 10, 100);
+        action.log(7, [3]);
+        action.track(7, 11, 101);
+        assert_eq!(action.entries, HashSet::from([1, 2, 3]));
+        assert_eq!(action.results.len(), 2);
+
+        action.log(8, [4]);
+        assert_eq!(action.id, 8);
+        assert_eq!(action.entries, HashSet::from([4]));
+        assert!(action.results.is_empty());
+
+        // A send of the old action reporting late isn't tracked anymore.
+        action.track(7, 12, 100);
+        action.report(12, &delivered(&[("stream", "abc")]));
+        assert!(action.results.is_empty());
+        assert!(action.message_ids.is_empty());
+    }
+
+    #[test]
+    fn reports_keep_outcomes_and_message_ids() {
+        let mut action = SendAction::default();
+        action.log(1, [1, 2, 3]);
+        action.track(1, 10, 100);
+        action.track(1, 11, 101);
+        action.track(1, 12, 102);
+
+        action.report(10, &delivered(&[("a", "id-a"), ("b", "id-b")]));
+        action.report(11, &Err("Timed out".to_string()));
+        action.forget(12, &HashSet::from([3]));
+
+        assert_eq!(action.results[&10], (100, Some(Ok(()))));
+        assert_eq!(action.results[&11], (101, Some(Err("Timed out".to_string()))));
+        assert!(!action.results.contains_key(&12));
+        assert_eq!(action.entries, HashSet::from([1, 2]));
+        assert_eq!(action.message_ids, vec![
+            (100, "a".to_string(), "id-a".to_string()),
+            (100, "b".to_string(), "id-b".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_std::{
+    io::{BufReader, WriteExt},
+    net::{TcpListener, TcpStream},
+    prelude::*,
+};
+use iced::futures::{SinkExt, Stream, channel::{mpsc, oneshot}};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+const MAX_BODY_SIZE: usize = 64 * 1024;
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+/// How long a client gets to deliver its whole request before it's dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SendRequest {
+    pub bot: String,
+    pub channel: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlastRequest {
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiRequest {
+    Send(SendRequest),
+    Blast(BlastRequest),
+    Status,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    pub fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    pub fn accepted() -> Self {
+        Self { status: 202, body: json!({ "queued": true }) }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self { status, body: json!({ "error": message }) }
+    }
+}
+
+/// One-shot reply channel carried inside `Message`, which has to be `Clone`.
+#[derive(Clone)]
+pub struct ApiResponder(Arc<Mutex<Option<oneshot::Sender<ApiResponse>>>>);
+
+impl ApiResponder {
+    pub fn respond(&self, response: ApiResponse) {
+        if let Some(sender) = self.0.lock().ok().and_then(|mut sender| sender.take()) {
+            let _ = sender.send(response);
+        }
+    }
+}
+
+impl fmt::Debug for ApiResponder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ApiResponder")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiEvent {
+    Request(ApiRequest, ApiResponder),
+    Failed(String),
+}
+
+/// Serves the control API on `127.0.0.1:<port>`. Every request must carry
+/// `Authorization: Bearer <token>`.
+pub fn listen(config: &(u16, String)) -> impl Stream<Item = ApiEvent> + use<> {
+    let (port, token) = config.clone();
+
+    iced::stream::channel(100, async move |mut output: mpsc::Sender<ApiEvent>| {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                let _ = output
+                    .send(ApiEvent::Failed(format!("Control API could not bind port {}: {}", port, e)))
+                    .await;
+                return;
+            }
+        };
+
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            if let Ok(stream) = stream {
+                let output = output.clone();
+                let token = token.clone();
+                async_std::task::spawn(async move {
+                    let _ = handle_connection(stream, &token, output).await;
+                });
+            }
+        }
+    })
+}
+
+struct RequestHead {
+    method: String,
+    path: String,
+    content_length: usize,
+    authorized: bool,
+}
+
+/// Reads one line without letting the headers as a whole grow past `MAX_HEADER_SIZE`.
+async fn read_header_line(
+    reader: &mut BufReader<&TcpStream>,
+    remaining: &mut usize,
+) -> anyhow::Result<String> {
+    let mut line = String::new();
+    let read = (&mut *reader).take(*remaining as u64).read_line(&mut line).await?;
+    *remaining -= read;
+    if read > 0 && !line.ends_with('\n') && *remaining == 0 {
+        anyhow::bail!("request headers too large");
+    }
+    Ok(line)
+}
+
+async fn read_head(reader: &mut BufReader<&TcpStream>, token: &str) -> anyhow::Result<RequestHead> {
+    let mut remaining = MAX_HEADER_SIZE;
+
+    let request_line = read_header_line(reader, &mut remaining).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut authorized = false;
+    loop {
+        let line = read_header_line(reader, &mut remaining).await?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                "authorization" => {
+                    authorized = value
+                        .strip_prefix("Bearer ")
+                        .is_some_and(|given| tokens_match(given.as_bytes(), token.as_bytes()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(RequestHead { method, path, content_length, authorized })
+}
+
+/// Compares without bailing at the first differing byte, so response timing
+/// doesn't reveal how much of a guessed token was right.
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    token: &str,
+    mut output: mpsc::Sender<ApiEvent>,
+) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+
+    let head = async_std::future::timeout(READ_TIMEOUT, read_head(&mut reader, token)).await??;
+
+    let response = if !head.authorized {
+        ApiResponse::error(401, "missing or invalid token")
+    } else if head.content_length > MAX_BODY_SIZE {
+        ApiResponse::error(413, "request body too large")
+    } else {
+        let mut body = vec![0u8; head.content_length];
+        async_std::future::timeout(READ_TIMEOUT, reader.read_exact(&mut body)).await??;
+
+        match parse_request(&head.method, &head.path, &body) {
+            Ok(request) => {
+                let (sender, receiver) = oneshot::channel();
+                let responder = ApiResponder(Arc::new(Mutex::new(Some(sender))));
+                output.send(ApiEvent::Request(request, responder)).await?;
+
+                match async_std::future::timeout(Duration::from_secs(5), receiver).await {
+                    Ok(Ok(response)) => response,
+                    _ => ApiResponse::error(504, "app did not respond"),
+                }
+            }
+            Err(response) => response,
+        }
+    };
+
+    let body = response.body.to_string();
+    writer
+        .write_all(
+            format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.status,
+                reason(response.status),
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn parse_request(method: &str, path: &str, body: &[u8]) -> Result<ApiRequest, ApiResponse> {
+    let invalid = |e: serde_json::Error| ApiResponse::error(400, &e.to_string());
+
+    match (method, path) {
+        ("POST", "/send") => serde_json::from_slice(body).map(ApiRequest::Send).map_err(invalid),
+        ("POST", "/blast") => serde_json::from_slice(body).map(ApiRequest::Blast).map_err(invalid),
+        ("GET", "/status") => Ok(ApiRequest::Status),
+        _ => Err(ApiResponse::error(404, "unknown endpoint")),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+
+const VALIDATE_URL: &str = "https://id.twitch.tv/oauth2/validate";
+const STREAMS_URL: &str = "https://api.twitch.tv/helix/streams";
+const USERS_URL: &str = "https://api.twitch.tv/helix/users";
+const WHISPERS_URL: &str = "https://api.twitch.tv/helix/whispers";
+const CHAT_MESSAGES_URL: &str = "https://api.twitch.tv/helix/moderation/chat";
+
+/// Needed to send messages.
+pub const SEND_SCOPE: &str = "chat:edit";
+/// Needed to read chat.
+pub const READ_SCOPE: &str = "chat:read";
+/// Needed to send whispers.
+pub const WHISPER_SCOPE: &str = "user:manage:whispers";
+/// Needed to delete chat messages as a moderator.
+pub const DELETE_SCOPE: &str = "moderator:manage:chat_messages";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenInfo {
+    pub client_id: String,
+    pub login: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_in: u64,
+}
+
+impl TokenInfo {
+    /// The chat scopes this token was not granted.
+    pub fn missing_chat_scopes(&self) -> Vec<String> {
+        [SEND_SCOPE, READ_SCOPE]
+            .into_iter()
+            .filter(|scope| !self.scopes.iter().any(|granted| granted == scope))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamsResponse {
+    data: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsersResponse {
+    data: Vec<HelixUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixUser {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+fn bearer(token: &str) -> &str {
+    token.strip_prefix("oauth:").unwrap_or(token)
+}
+
+/// Checks a user token against the validate endpoint, returning the login,
+/// scopes and remaining lifetime it was issued with.
+pub async fn validate_token(token: &str) -> Result<TokenInfo> {
+    validate_with(&reqwest::Client::new(), token).await
+}
+
+async fn validate_with(client: &reqwest::Client, token: &str) -> Result<TokenInfo> {
+    let response = client
+        .get(VALIDATE_URL)
+        .header("Authorization", format!("OAuth {}", bearer(token)))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Token validation failed ({})", response.status()));
+    }
+
+    Ok(response.json::<TokenInfo>().await?)
+}
+
+/// Returns whether `channel` is currently streaming, using `token` to
+/// authenticate against Helix.
+pub async fn is_channel_live(token: &str, channel: &str) -> Result<bool> {
+    let client = reqwest::Client::new();
+    // Helix calls need the client id the token was issued for.
+    let client_id = validate_with(&client, token).await?.client_id;
+
+    let response = client
+        .get(STREAMS_URL)
+        .query(&[("user_login", channel.to_lowercase())])
+        .header("Client-Id", client_id)
+        .bearer_auth(bearer(token))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Stream status request failed ({})", response.status()));
+    }
+
+    Ok(!response.json::<StreamsResponse>().await?.data.is_empty())
+}
+
+/// Looks up the user id of `login`.
+async fn user_id(client: &reqwest::Client, info: &TokenInfo, token: &str, login: &str) -> Result<String> {
+    let users = client
+        .get(USERS_URL)
+        .query(&[("login", login.trim_start_matches(['@', '#']).to_lowercase())])
+        .header("Client-Id", &info.client_id)
+        .bearer_auth(bearer(token))
+        .send()
+        .await?;
+    if !users.status().is_success() {
+        return Err(anyhow!("User lookup failed ({})", users.status()));
+    }
+    Ok(users
+        .json::<UsersResponse>()
+        .await?
+        .data
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No Twitch user named {}", login))?
+        .id)
+}
+
+/// Deletes the chat message `message_id` in `channel`, acting as the
+/// token's account, which has to be a moderator there.
+pub async fn delete_chat_message(token: &str, channel: &str, message_id: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let info = validate_with(&client, token).await?;
+    if !info.scopes.iter().any(|scope| scope == DELETE_SCOPE) {
+        return Err(anyhow!("This token can't delete messages — missing {} scope", DELETE_SCOPE));
+    }
+    let broadcaster_id = user_id(&client, &info, token, channel).await?;
+
+    let response = client
+        .delete(CHAT_MESSAGES_URL)
+        .query(&[
+            ("broadcaster_id", broadcaster_id.as_str()),
+            ("moderator_id", info.user_id.as_str()),
+            ("message_id", message_id),
+        ])
+        .header("Client-Id", &info.client_id)
+        .bearer_auth(bearer(token))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let reason = response.json::<ErrorResponse>().await.map(|e| e.message).unwrap_or_default();
+    Err(anyhow!("Delete in #{} failed ({}): {}", channel, status, reason))
+}
+
+/// Whispers `message` to `target` from the token's account. Twitch only lets
+/// accounts with a verified phone number whisper and caps how many whispers
+/// and new recipients an account gets; hitting either comes back as an error.
+pub async fn send_whisper(token: &str, target: &str, message: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let info = validate_with(&client, token).await?;
+    if !info.scopes.iter().any(|scope| scope == WHISPER_SCOPE) {
+        return Err(anyhow!("This token can't whisper — missing {} scope", WHISPER_SCOPE));
+    }
+
+    let to_user_id = user_id(&client, &info, token, target).await?;
+
+    let response = client
+        .post(WHISPERS_URL)
+        .query(&[("from_user_id", info.user_id.as_str()), ("to_user_id", to_user_id.as_str())])
+        .header("Client-Id", &info.client_id)
+        .bearer_auth(bearer(token))
+        .json(&json!({ "message": message }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let reason = response.json::<ErrorResponse>().await.map(|e| e.message).unwrap_or_default();
+    match status {
+        reqwest::StatusCode::FORBIDDEN => Err(anyhow!(
+            "This account isn't allowed to whisper, it may need a verified phone number ({})",
+            reason
+        )),
+        reqwest::StatusCode::TOO_MANY_REQUESTS => Err(anyhow!("Whisper rate limit reached ({})", reason)),
+        _ => Err(anyhow!("Whisper failed ({}): {}", status, reason)),
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+
+pub const DEFAULT_MAX_HISTORY: usize = 1000;
+
+static MAX_HISTORY: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_HISTORY);
+static ENTRY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Sets how many entries every chat log keeps before dropping the oldest.
+pub fn set_max_history(max: usize) {
+    MAX_HISTORY.store(max.max(1), Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Sent,
+    /// Sent by a bot picked at random, shown with a 🎲.
+    RandomSent,
+    /// A chat message that mentioned the bot.
+    Received,
+    Error,
+    Info,
+}
+
+impl EntryKind {
+    /// The name written to exported CSV files.
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryKind::Sent => "sent",
+            EntryKind::RandomSent => "random",
+            EntryKind::Received => "received",
+            EntryKind::Error => "error",
+            EntryKind::Info => "info",
+        }
+    }
+}
+
+/// One line of the global or a bot's chat history.
+#[derive(Debug, Clone)]
+pub struct ChatEntry {
+    /// Shared by the copies of an entry in the global and bot histories,
+    /// so both can be found again.
+    pub id: u64,
+    pub time: DateTime<Local>,
+    pub kind: EntryKind,
+    /// The bot the entry is about; empty for app-wide notices.
+    pub bot: String,
+    /// That bot's id, which unlike its name is unique.
+    pub bot_id: Option<u64>,
+    /// Set when a send went to several channels at once.
+    pub channel: Option<String>,
+    pub text: String,
+}
+
+impl ChatEntry {
+    fn new(kind: EntryKind, bot: String, text: String) -> Self {
+        Self {
+            id: ENTRY_COUNTER.fetch_add(1, Ordering::Relaxed),
+            time: Local::now(),
+            kind,
+            bot,
+            bot_id: None,
+            channel: None,
+            text,
+        }
+    }
+
+    pub fn sent(bot_id: u64, bot: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(EntryKind::Sent, String::new(), text.into()).with_bot(bot_id, bot)
+    }
+
+    pub fn random_sent(bot_id: u64, bot: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(EntryKind::RandomSent, String::new(), text.into()).with_bot(bot_id, bot)
+    }
+
+    pub fn received(bot_id: u64, bot: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(EntryKind::Received, String::new(), text.into()).with_bot(bot_id, bot)
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self::new(EntryKind::Error, String::new(), text.into())
+    }
+
+    pub fn info(text: impl Into<String>) -> Self {
+        Self::new(EntryKind::Info, String::new(), text.into())
+    }
+
+    pub fn with_bot(mut self, bot_id: u64, bot: impl Into<String>) -> Self {
+        self.bot_id = Some(bot_id);
+        self.bot = bot.into();
+        self
+    }
+
+    pub fn with_channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel = Some(channel.into());
+        self
+    }
+
+    /// The text as shown after the bot name, with errors marked.
+    pub fn body(&self) -> String {
+        match self.kind {
+            EntryKind::Error => format!("❌ Error: {}", self.text),
+            EntryKind::Received => format!("💬 {}", self.text),
+            _ => self.text.clone(),
+        }
+    }
+
+    /// Whether this comes right after `previous` from the same bot, so the
+    /// chat can group it under that entry.
+    pub fn continues(&self, previous: &ChatEntry) -> bool {
+        !self.bot.is_empty()
+            && self.bot == previous.bot
+            && self.kind != EntryKind::Info
+            && previous.kind != EntryKind::Info
+    }
+
+    /// The line without the bot name, for entries grouped under the one
+    /// before.
+    pub fn without_bot(&self) -> String {
+        match &self.channel {
+            Some(channel) => format!("[{}] [#{}] {}", self.time.format("%H:%M:%S"), channel, self.body()),
+            None => format!("[{}] {}", self.time.format("%H:%M:%S"), self.body()),
+        }
+    }
+
+    /// Whether the bot name, channel or text contains `query`, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.bot.to_lowercase().contains(&query)
+            || self.text.to_lowercase().contains(&query)
+            || self.channel.as_ref().is_some_and(|channel| channel.to_lowercase().contains(&query))
+    }
+}
+
+/// A chat history, oldest first. Pushing past the history limit drops the
+/// oldest entries so long runs don't grow without bound.
+#[derive(Debug, Clone, Default)]
+pub struct ChatLog(VecDeque<ChatEntry>);
+
+impl ChatLog {
+    pub fn push(&mut self, entry: ChatEntry) {
+        let max = MAX_HISTORY.load(Ordering::Relaxed);
+        while self.0.len() >= max {
+            self.0.pop_front();
+        }
+        self.0.push_back(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The entry with `id`, if it hasn't been dropped yet.
+    pub fn find(&self, id: u64) -> Option<&ChatEntry> {
+        self.0.iter().find(|entry| entry.id == id)
+    }
+
+    /// Drops the entries with the given ids and returns how many went.
+    pub fn remove_ids(&mut self, ids: &HashSet<u64>) -> usize {
+        let before = self.0.len();
+        self.0.retain(|entry| !ids.contains(&entry.id));
+        before - self.0.len()
+    }
+
+    /// The retained entries, for exporting.
+    pub fn to_vec(&self) -> Vec<ChatEntry> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+impl Deref for ChatLog {
+    type Target = VecDeque<ChatEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for ChatEntry {
+    /// `[HH:MM:SS] [bot] text`, with `[#channel]` before the bot for
+    /// multi-channel sends.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.time.format("%H:%M:%S"))?;
+        if let Some(channel) = &self.channel {
+            write!(f, "[#{}] ", channel)?;
+        }
+        match (self.kind, self.bot.is_empty()) {
+            (EntryKind::RandomSent, _) => write!(f, "[🎲 {}] ", self.bot)?,
+            (_, false) => write!(f, "[{}] ", self.bot)?,
+            (_, true) => {}
+        }
+        f.write_str(&self.body())
+    }
+}
+
+/// Writes history to `path`: line by line for `.txt`, or as
+/// `timestamp,bot,channel,kind,message` rows for `.csv`. The channel is
+/// empty unless the send went to several channels.
+pub fn export_history(path: &Path, entries: &[ChatEntry]) -> Result<()> {
+    let is_csv = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    if !is_csv {
+        let lines: Vec<String> = entries.iter().map(ChatEntry::to_string).collect();
+        std::fs::write(path, lines.join("\n"))?;
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["timestamp", "bot", "channel", "kind", "message"])?;
+    for entry in entries {
+        writer.write_record([
+            entry.time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            entry.bot.clone(),
+            entry.channel.clone().unwrap_or_default(),
+            entry.kind.label().to_string(),
+            entry.body(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at_noon(entry: ChatEntry) -> ChatEntry {
+        ChatEntry {
+            time: Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 5).unwrap(),
+            ..entry
+        }
+    }
+
+    #[test]
+    fn display_puts_channel_before_bot() {
+        let plain = at_noon(ChatEntry::sent(1, "mybot", "hello"));
+        let multi = at_noon(ChatEntry::random_sent(1, "mybot", "hello").with_channel("stream"));
+        let notice = at_noon(ChatEntry::error("no bots").with_channel("stream"));
+
+        assert_eq!(plain.to_string(), "[12:00:05] [mybot] hello");
+        assert_eq!(multi.to_string(), "[12:00:05] [#stream] [🎲 mybot] hello");
+        assert_eq!(notice.to_string(), "[12:00:05] [#stream] ❌ Error: no bots");
+    }
+
+    #[test]
+    fn csv_export_has_channel_and_kind() {
+        let entries = [
+            at_noon(ChatEntry::random_sent(1, "mybot", "hello, chat").with_channel("stream")),
+            at_noon(ChatEntry::info("📄 Loaded 2 messages")),
+        ];
+        let path = std::env::temp_dir().join(format!("ngs_history_export_{}.csv", std::process::id()));
+
+        export_history(&path, &entries).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, concat!(
+            "timestamp,bot,channel,kind,message\n",
+            "2024-05-01 12:00:05,mybot,stream,random,\"hello, chat\"\n",
+            "2024-05-01 12:00:05,,,info,📄 Loaded 2 messages\n",
+        ));
+    }
+}
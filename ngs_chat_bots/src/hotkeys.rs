@@ -0,0 +1,135 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use iced::keyboard::{self, Key, key::Named};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    RandomNow,
+    AllBots,
+    Single,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 3] = [
+        HotkeyAction::RandomNow,
+        HotkeyAction::AllBots,
+        HotkeyAction::Single,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::RandomNow => "Send random now",
+            HotkeyAction::AllBots => "Send with all bots",
+            HotkeyAction::Single => "Send with one bot",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hotkey {
+    pub key: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Hotkey {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    /// Builds a hotkey from a key press, ignoring repeats and lone modifier keys.
+    pub fn from_event(event: keyboard::Event) -> Option<Self> {
+        let keyboard::Event::KeyPressed { key, modifiers, repeat: false, .. } = event else {
+            return None;
+        };
+
+        let key = match key {
+            Key::Character(c) => c.to_uppercase(),
+            Key::Named(Named::Control | Named::Shift | Named::Alt | Named::Super) => return None,
+            Key::Named(named) => format!("{:?}", named),
+            Key::Unidentified => return None,
+        };
+
+        Some(Self {
+            key,
+            ctrl: modifiers.control(),
+            shift: modifiers.shift(),
+            alt: modifiers.alt(),
+        })
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub send_random_now: Option<Hotkey>,
+    pub send_all_bots: Option<Hotkey>,
+    pub send_single: Option<Hotkey>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            send_random_now: Some(Hotkey::new("F5")),
+            send_all_bots: Some(Hotkey::new("F6")),
+            send_single: Some(Hotkey::new("F7")),
+        }
+    }
+}
+
+impl Hotkeys {
+    pub fn get(&self, action: HotkeyAction) -> Option<&Hotkey> {
+        match action {
+            HotkeyAction::RandomNow => self.send_random_now.as_ref(),
+            HotkeyAction::AllBots => self.send_all_bots.as_ref(),
+            HotkeyAction::Single => self.send_single.as_ref(),
+        }
+    }
+
+    /// Binds `hotkey` to `action`, unbinding it from any other action first.
+    pub fn set(&mut self, action: HotkeyAction, hotkey: Option<Hotkey>) {
+        if let Some(new) = &hotkey {
+            for other in HotkeyAction::ALL {
+                if self.get(other) == Some(new) {
+                    *self.slot(other) = None;
+                }
+            }
+        }
+        *self.slot(action) = hotkey;
+    }
+
+    pub fn action_for(&self, hotkey: &Hotkey) -> Option<HotkeyAction> {
+        HotkeyAction::ALL
+            .into_iter()
+            .find(|&action| self.get(action) == Some(hotkey))
+    }
+
+    fn slot(&mut self, action: HotkeyAction) -> &mut Option<Hotkey> {
+        match action {
+            HotkeyAction::RandomNow => &mut self.send_random_now,
+            HotkeyAction::AllBots => &mut self.send_all_bots,
+            HotkeyAction::Single => &mut self.send_single,
+        }
+    }
+}
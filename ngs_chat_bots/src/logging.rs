@@ -0,0 +1,71 @@
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{Result, anyhow};
+use crate::session::config_dir;
+
+/// A log bigger than this is moved to `ngs_chat_bots.log.1` at startup,
+/// replacing the previous one.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// The log file, next to `settings.json`.
+pub fn log_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("ngs_chat_bots.log"))
+}
+
+/// Starts logging to the log file. Release builds have no console on
+/// Windows, so the file is the only place connection problems show up.
+/// `RUST_LOG` overrides the default `info` level; stderr is used if the
+/// file can't be opened.
+pub fn init() {
+    let mut builder = env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("warn,ngs_chat_bots=info")
+    );
+    // A failed rotation, e.g. while another copy has the file open, keeps
+    // appending to the current log rather than losing it.
+    let rotated = log_path().map(|path| rotate_log(&path));
+    if let Some(file) = log_path().and_then(|path| open_log(&path).ok()) {
+        builder.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+    builder.init();
+
+    if let Some(Err(e)) = rotated {
+        log::warn!("Could not rotate the log: {}", e);
+    }
+}
+
+/// Moves a log over `MAX_LOG_SIZE` to `ngs_chat_bots.log.1`.
+fn rotate_log(path: &Path) -> std::io::Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_LOG_SIZE) {
+        fs::rename(path, path.with_extension("log.1"))?;
+    }
+    Ok(())
+}
+
+fn open_log(path: &Path) -> std::io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Opens the log file in the system's default viewer.
+pub fn open_log_file() -> Result<()> {
+    let path = log_path().ok_or_else(|| anyhow!("No config directory available"))?;
+    if !path.exists() {
+        return Err(anyhow!("No log file yet at {}", path.display()));
+    }
+
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(&path).spawn()?;
+    Ok(())
+}
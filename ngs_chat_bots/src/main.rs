@@ -4,10 +4,24 @@ use crate::app::App;
 
 mod twitch_utils;
 mod app;
+mod control_api;
+mod helix;
+mod history;
+mod hotkeys;
+mod logging;
+mod messages;
+mod presets;
+mod schedule;
+mod session;
+mod settings;
+mod theme;
 
 fn main() {
+    logging::init();
+
     iced::application(App::new, App::update, App::view)
         .subscription(App::subscription)
-        .exit_on_close_request(true)
+        .theme(App::theme)
+        .exit_on_close_request(false)
         .run();
 }
\ No newline at end of file
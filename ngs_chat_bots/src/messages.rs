@@ -0,0 +1,237 @@
+use std::collections::BTreeSet;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A message line loaded from a CSV or JSON file, with the metadata that
+/// plain `.txt` lists can't carry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageEntry {
+    pub text: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub cooldown: u64,
+    #[serde(default)]
+    pub list: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(skip)]
+    pub last_used: Option<Instant>,
+}
+
+impl MessageEntry {
+    /// A line with no metadata, as if it came from a `.txt` list.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            weight: default_weight(),
+            cooldown: 0,
+            list: None,
+            enabled: default_enabled(),
+            last_used: None,
+        }
+    }
+
+    pub fn is_ready(&self, now: Instant) -> bool {
+        if !self.enabled || self.weight == 0 {
+            return false;
+        }
+
+        match self.last_used {
+            Some(last) => now.duration_since(last).as_secs() >= self.cooldown,
+            None => true,
+        }
+    }
+}
+
+pub fn parse_messages_json(content: &str) -> Result<Vec<MessageEntry>> {
+    let entries: Vec<MessageEntry> = serde_json::from_str(content)?;
+    Ok(entries.into_iter().filter(|entry| !entry.text.trim().is_empty()).collect())
+}
+
+/// Parses a CSV file with a header row. Only the `text` column is required;
+/// `weight`, `cooldown`, `list` and `enabled` are optional.
+pub fn parse_messages_csv(content: &str) -> Result<Vec<MessageEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut entries = Vec::new();
+    for record in reader.deserialize() {
+        let entry: MessageEntry = record?;
+        if !entry.text.is_empty() {
+            entries.push(entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+pub fn write_messages_json(entries: &[MessageEntry]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(entries)?)
+}
+
+/// Writes the columns `parse_messages_csv` reads, with a header row.
+pub fn write_messages_csv(entries: &[MessageEntry]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for entry in entries {
+        writer.serialize(entry)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Reads a blocklist, one term per line. Terms are matched ignoring case.
+pub fn parse_blocklist(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .map(|line| fold_case(line.trim()))
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Lowercases one char at a time, so positions line up with the original.
+fn fold_case(text: &str) -> String {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+/// The blocklist term that starts earliest in `message`, the longest one
+/// when several start at the same place.
+pub fn find_blocked<'a>(message: &str, blocklist: &'a BTreeSet<String>) -> Option<&'a str> {
+    let folded = fold_case(message);
+    blocklist
+        .iter()
+        .filter_map(|term| folded.find(term.as_str()).map(|start| (start, term)))
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.len().cmp(&a.1.len())))
+        .map(|(_, term)| term.as_str())
+}
+
+/// Replaces every blocklist term in `message` with asterisks.
+pub fn mask_blocked(message: &str, blocklist: &BTreeSet<String>) -> String {
+    let chars: Vec<char> = message.chars().collect();
+    let folded: Vec<char> = fold_case(message).chars().collect();
+    let mut masked = vec![false; chars.len()];
+
+    for term in blocklist {
+        let term: Vec<char> = term.chars().collect();
+        if term.len() > folded.len() {
+            continue;
+        }
+        for start in 0..=folded.len() - term.len() {
+            if folded[start..start + term.len()] == term[..] {
+                masked[start..start + term.len()].fill(true);
+            }
+        }
+    }
+
+    chars
+        .into_iter()
+        .zip(masked)
+        .map(|(c, masked)| if masked { '*' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sample() -> Vec<MessageEntry> {
+        vec![
+            MessageEntry { weight: 3, cooldown: 30, list: Some("hype".to_string()), ..MessageEntry::new("PogChamp, \"quoted\"") },
+            MessageEntry { enabled: false, ..MessageEntry::new("gg") },
+            MessageEntry::new("hello chat"),
+        ]
+    }
+
+    fn assert_same(parsed: &[MessageEntry], expected: &[MessageEntry]) {
+        assert_eq!(parsed.len(), expected.len());
+        for (parsed, expected) in parsed.iter().zip(expected) {
+            assert_eq!(parsed.text, expected.text);
+            assert_eq!(parsed.weight, expected.weight);
+            assert_eq!(parsed.cooldown, expected.cooldown);
+            assert_eq!(parsed.list, expected.list);
+            assert_eq!(parsed.enabled, expected.enabled);
+        }
+    }
+
+    #[test]
+    fn csv_fills_in_missing_columns_and_skips_empty_text() {
+        let parsed = parse_messages_csv("text,weight\n hello , 4\n,2\nbye,1\n").unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[0].text.as_str(), parsed[0].weight), ("hello", 4));
+        assert!(parsed[0].enabled);
+        assert_eq!(parsed[1].text, "bye");
+        assert!(parse_messages_csv("text,weight\nhello,lots\n").is_err());
+    }
+
+    #[test]
+    fn json_needs_only_text() {
+        let parsed = parse_messages_json(r#"[{"text": "hello"}, {"text": "  "}, {"text": "gg", "enabled": false}]"#).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[0].weight, parsed[0].cooldown, parsed[0].list.as_deref()), (1, 0, None));
+        assert!(!parsed[1].enabled);
+        assert!(parse_messages_json(r#"[{"weight": 2}]"#).is_err());
+    }
+
+    #[test]
+    fn ready_after_cooldown_when_enabled_and_weighted() {
+        let now = Instant::now();
+        let mut entry = MessageEntry { cooldown: 10, ..MessageEntry::new("hello") };
+        assert!(entry.is_ready(now));
+
+        entry.last_used = Some(now);
+        assert!(!entry.is_ready(now + Duration::from_secs(9)));
+        assert!(entry.is_ready(now + Duration::from_secs(10)));
+
+        assert!(!MessageEntry { weight: 0, ..MessageEntry::new("hello") }.is_ready(now));
+        assert!(!MessageEntry { enabled: false, ..MessageEntry::new("hello") }.is_ready(now));
+    }
+
+    #[test]
+    fn finds_earliest_then_longest_term() {
+        let blocklist = parse_blocklist("spam\n\n  SCAM \nscammer\n");
+
+        assert_eq!(blocklist.len(), 3);
+        assert_eq!(find_blocked("Big SCAMMER and spam", &blocklist), Some("scammer"));
+        assert_eq!(find_blocked("spam then scam", &blocklist), Some("spam"));
+        assert_eq!(find_blocked("all clear", &blocklist), None);
+    }
+
+    #[test]
+    fn masks_every_occurrence_keeping_case_elsewhere() {
+        let blocklist = parse_blocklist("spam\nscam");
+
+        assert_eq!(mask_blocked("SPAM and Scam, spam!", &blocklist), "**** and ****, ****!");
+        assert_eq!(mask_blocked("Ünïcode spam ok", &blocklist), "Ünïcode **** ok");
+        assert_eq!(mask_blocked("sp", &blocklist), "sp");
+    }
+
+    #[test]
+    fn csv_round_trips_metadata() {
+        let entries = sample();
+        let written = write_messages_csv(&entries).unwrap();
+        let parsed = parse_messages_csv(&written).unwrap();
+        assert_same(&parsed, &entries);
+        assert_eq!(write_messages_csv(&parsed).unwrap(), written);
+    }
+
+    #[test]
+    fn json_round_trips_metadata() {
+        let entries = sample();
+        let written = write_messages_json(&entries).unwrap();
+        let parsed = parse_messages_json(&written).unwrap();
+        assert_same(&parsed, &entries);
+        assert_eq!(write_messages_json(&parsed).unwrap(), written);
+    }
+}
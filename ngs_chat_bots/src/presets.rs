@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, anyhow};
+use crate::session::config_dir;
+
+/// A named snapshot of the send settings: modes, intervals, delays and
+/// counts. Applying one overwrites all of them at once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendPreset {
+    pub all_bots_mode: bool,
+    pub simultaneous_mode: bool,
+    pub multiple_bots_mode: bool,
+    pub multiple_bots_count: usize,
+    pub min_interval: u64,
+    pub max_interval: u64,
+    pub min_bot_delay: u64,
+    pub max_bot_delay: u64,
+    pub clear_after_send: bool,
+    pub auto_split: bool,
+    pub number_split_parts: bool,
+}
+
+fn presets_path() -> Result<PathBuf> {
+    Ok(config_dir()
+        .ok_or_else(|| anyhow!("No config directory available"))?
+        .join("presets.json"))
+}
+
+/// Loads saved presets. A missing file just means none were saved yet.
+pub fn load_presets() -> Result<BTreeMap<String, SendPreset>> {
+    let path = presets_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+pub fn save_presets(presets: &BTreeMap<String, SendPreset>) -> Result<()> {
+    let path = presets_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(presets)?)?;
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Duration, Local, NaiveTime};
+
+/// A message sent at a set clock time, once or on a repeat.
+#[derive(Debug, Clone)]
+pub struct ScheduledMessage {
+    pub at: DateTime<Local>,
+    pub text: String,
+    /// The bot to send from; `None` uses the single-send policy.
+    pub bot: Option<u64>,
+    pub repeat: Option<Duration>,
+}
+
+impl ScheduledMessage {
+    pub fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.at <= now
+    }
+
+    /// Moves a repeating schedule to its next time after `now`, skipping
+    /// runs missed while the app was busy. Returns `false` for one-off
+    /// schedules, which are done.
+    pub fn advance(&mut self, now: DateTime<Local>) -> bool {
+        let Some(repeat) = self.repeat.filter(|repeat| *repeat > Duration::zero()) else {
+            return false;
+        };
+        while self.at <= now {
+            self.at += repeat;
+        }
+        true
+    }
+}
+
+/// The next time the clock shows `HH:MM` (or `HH:MM:SS`), today or
+/// tomorrow.
+pub fn next_time_at(input: &str) -> Option<DateTime<Local>> {
+    let input = input.trim();
+    let time = NaiveTime::parse_from_str(input, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M"))
+        .ok()?;
+
+    let now = Local::now();
+    let today = now.date_naive().and_time(time).and_local_timezone(Local).earliest()?;
+    if today > now {
+        Some(today)
+    } else {
+        (now.date_naive() + Duration::days(1))
+            .and_time(time)
+            .and_local_timezone(Local)
+            .earliest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every(minutes: i64, at: DateTime<Local>) -> ScheduledMessage {
+        ScheduledMessage {
+            at,
+            text: "hello".to_string(),
+            bot: None,
+            repeat: Some(Duration::minutes(minutes)),
+        }
+    }
+
+    #[test]
+    fn repeat_skips_missed_runs() {
+        let now = Local::now();
+        let mut scheduled = every(10, now - Duration::minutes(25));
+
+        assert!(scheduled.is_due(now));
+        assert!(scheduled.advance(now));
+        assert_eq!(scheduled.at, now + Duration::minutes(5));
+        assert!(!scheduled.is_due(now));
+    }
+
+    #[test]
+    fn one_off_and_zero_repeat_are_done() {
+        let now = Local::now();
+        let mut once = ScheduledMessage { repeat: None, ..every(10, now) };
+        let mut zero = every(0, now);
+
+        assert!(!once.advance(now));
+        assert!(!zero.advance(now));
+        assert_eq!(zero.at, now);
+    }
+
+    #[test]
+    fn next_time_is_within_a_day() {
+        let before = Local::now();
+        let at = next_time_at(" 07:30 ").unwrap();
+
+        assert!(at > before && at <= before + Duration::days(1) + Duration::hours(1));
+        assert_eq!(at.format("%H:%M:%S").to_string(), "07:30:00");
+        assert_eq!(next_time_at("23:59:58").unwrap().format("%H:%M:%S").to_string(), "23:59:58");
+        assert!(next_time_at("25:00").is_none());
+        assert!(next_time_at("noon").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::Instant;
+use chrono::{DateTime, Local};
+use directories::ProjectDirs;
+use anyhow::Result;
+
+pub fn config_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "ZikQ", "ngs_chat_bots").map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Sends, failures and mentions of one bot since the stats were last reset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BotStats {
+    pub sent: usize,
+    pub failed: usize,
+    pub received: usize,
+}
+
+impl BotStats {
+    pub fn total(&self) -> usize {
+        self.sent + self.failed
+    }
+}
+
+/// Running totals for the current session, written out as a summary on exit.
+#[derive(Debug)]
+pub struct SessionStats {
+    started_at: DateTime<Local>,
+    started: Instant,
+    sent: BTreeMap<String, usize>,
+    failed: BTreeMap<String, usize>,
+    errors: HashMap<String, usize>,
+    channels: BTreeSet<String>,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self {
+            started_at: Local::now(),
+            started: Instant::now(),
+            sent: BTreeMap::new(),
+            failed: BTreeMap::new(),
+            errors: HashMap::new(),
+            channels: BTreeSet::new(),
+        }
+    }
+
+    pub fn record_channel(&mut self, channel: &str) {
+        if !channel.is_empty() {
+            self.channels.insert(channel.to_string());
+        }
+    }
+
+    pub fn record_sent(&mut self, bot: &str) {
+        *self.sent.entry(bot.to_string()).or_default() += 1;
+    }
+
+    pub fn record_failed(&mut self, bot: &str, error: &str) {
+        *self.failed.entry(bot.to_string()).or_default() += 1;
+        *self.errors.entry(error.to_string()).or_default() += 1;
+    }
+
+    /// Takes back a `record_sent` for a send that was undone.
+    pub fn unrecord_sent(&mut self, bot: &str) {
+        if let Some(count) = self.sent.get_mut(bot) {
+            *count -= 1;
+            if *count == 0 {
+                self.sent.remove(bot);
+            }
+        }
+    }
+
+    /// Takes back a `record_failed` for a send that was undone.
+    pub fn unrecord_failed(&mut self, bot: &str, error: &str) {
+        if let Some(count) = self.failed.get_mut(bot) {
+            *count -= 1;
+            if *count == 0 {
+                self.failed.remove(bot);
+            }
+        }
+        if let Some(count) = self.errors.get_mut(error) {
+            *count -= 1;
+            if *count == 0 {
+                self.errors.remove(error);
+            }
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let total_sent: usize = self.sent.values().sum();
+        let total_failed: usize = self.failed.values().sum();
+        let duration = self.started.elapsed().as_secs();
+
+        let mut lines = vec![
+            format!("Session started: {}", self.started_at.format("%Y-%m-%d %H:%M:%S")),
+            format!(
+                "Duration: {:02}:{:02}:{:02}",
+                duration / 3600,
+                duration / 60 % 60,
+                duration % 60
+            ),
+            format!("Messages sent: {}", total_sent),
+            format!("Failures: {}", total_failed),
+            format!(
+                "Channels: {}",
+                if self.channels.is_empty() {
+                    "-".to_string()
+                } else {
+                    self.channels.iter().cloned().collect::<Vec<_>>().join(", ")
+                }
+            ),
+        ];
+
+        if !self.sent.is_empty() || !self.failed.is_empty() {
+            lines.push(String::new());
+            lines.push("Per bot (sent / failed):".to_string());
+
+            let bots: BTreeSet<&String> = self.sent.keys().chain(self.failed.keys()).collect();
+            for bot in bots {
+                lines.push(format!(
+                    "  {}: {} / {}",
+                    bot,
+                    self.sent.get(bot).unwrap_or(&0),
+                    self.failed.get(bot).unwrap_or(&0)
+                ));
+            }
+        }
+
+        if !self.errors.is_empty() {
+            let mut errors: Vec<(&String, &usize)> = self.errors.iter().collect();
+            errors.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+            lines.push(String::new());
+            lines.push("Top errors:".to_string());
+            for (error, count) in errors.into_iter().take(5) {
+                lines.push(format!("  {}x {}", count, error));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Writes the summary to `sessions/session-<date>.txt` in the config dir.
+    pub fn save_summary(&self) -> Result<PathBuf> {
+        let dir = config_dir()
+            .ok_or_else(|| anyhow::anyhow!("No config directory available"))?
+            .join("sessions");
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!(
+            "session-{}.txt",
+            self.started_at.format("%Y-%m-%d_%H-%M-%S")
+        ));
+        std::fs::write(&path, self.summary())?;
+
+        Ok(path)
+    }
+}
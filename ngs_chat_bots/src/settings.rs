@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use anyhow::{Result, anyhow};
+use crate::history::DEFAULT_MAX_HISTORY;
+use crate::hotkeys::Hotkeys;
+use crate::presets::SendPreset;
+use crate::session::config_dir;
+use crate::theme::Theme;
+use crate::twitch_utils::{HealthWeights, Timeouts};
+
+/// App settings kept between sessions in `settings.json`. Fields missing
+/// from the file get their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Put into the message box when the app starts.
+    pub default_message: String,
+    /// Target of sandbox sends from the bot chat view. Empty means each
+    /// bot's own channel.
+    pub test_channel: String,
+    /// Weights of the bot health score.
+    pub health_weights: HealthWeights,
+    /// Network waits of connection tests and sends.
+    pub timeouts: Timeouts,
+    /// Connect to IRC over TLS on port 6697.
+    pub use_tls: bool,
+    pub theme: Theme,
+    pub hotkeys: Hotkeys,
+    /// Which of the bots, editor and chat panels are shown.
+    pub layout: Layout,
+    /// Lowercased terms no message may contain.
+    pub blocklist: BTreeSet<String>,
+    /// Star out blocked terms instead of refusing the whole message.
+    pub mask_blocked: bool,
+    /// Ask before sends that go out from many bots at once.
+    pub confirm_mass_sends: bool,
+    /// How many bots "Check Bots" tests at the same time. Twitch
+    /// rate-limits an IP that opens too many connections at once.
+    pub max_concurrent_checks: usize,
+    /// Entries kept in the global chat and in each bot's history.
+    pub max_history: usize,
+    /// Channel and send settings as they were when the app last closed.
+    pub channel: String,
+    pub send: Option<SendPreset>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub show_bots: bool,
+    pub show_editor: bool,
+    pub show_chat: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            show_bots: true,
+            show_editor: true,
+            show_chat: true,
+        }
+    }
+}
+
+fn default_max_concurrent_checks() -> usize {
+    10
+}
+
+fn default_max_history() -> usize {
+    DEFAULT_MAX_HISTORY
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_message: String::new(),
+            test_channel: String::new(),
+            health_weights: HealthWeights::default(),
+            timeouts: Timeouts::default(),
+            use_tls: false,
+            theme: Theme::default(),
+            hotkeys: Hotkeys::default(),
+            layout: Layout::default(),
+            blocklist: BTreeSet::new(),
+            mask_blocked: false,
+            confirm_mass_sends: false,
+            max_concurrent_checks: default_max_concurrent_checks(),
+            max_history: default_max_history(),
+            channel: String::new(),
+            send: None,
+        }
+    }
+}
+
+fn settings_path() -> Result<PathBuf> {
+    Ok(config_dir()
+        .ok_or_else(|| anyhow!("No config directory available"))?
+        .join("settings.json"))
+}
+
+impl Settings {
+    /// Loads the saved settings, or the defaults if none were saved yet.
+    pub fn load() -> Result<Self> {
+        let path = settings_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Reads saved settings. A field that doesn't parse, say from a hand
+    /// edit or an older version, is logged and left at its default instead
+    /// of losing every other setting with it.
+    fn parse(content: &str) -> Result<Self> {
+        let saved: Value = serde_json::from_str(content)?;
+        let error = match serde_json::from_value(saved.clone()) {
+            Ok(settings) => return Ok(settings),
+            Err(e) => e,
+        };
+        log::warn!("Some saved settings could not be read: {}", error);
+
+        let Value::Object(fields) = saved else {
+            return Err(error.into());
+        };
+        let mut kept = Map::new();
+        for (key, value) in fields {
+            let single = Value::Object(Map::from_iter([(key.clone(), value.clone())]));
+            match serde_json::from_value::<Self>(single) {
+                Ok(_) => {
+                    kept.insert(key, value);
+                },
+                Err(e) => log::warn!("Using the default for setting `{}`: {}", key, e),
+            }
+        }
+        Ok(serde_json::from_value(Value::Object(kept))?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = settings_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_field_keeps_the_other_settings() {
+        let settings = Settings::parse(r#"{"channel": "stream", "max_history": "lots", "use_tls": true}"#).unwrap();
+
+        assert_eq!(settings.channel, "stream");
+        assert!(settings.use_tls);
+        assert_eq!(settings.max_history, DEFAULT_MAX_HISTORY);
+        assert!(Settings::parse("not json").is_err());
+    }
+}
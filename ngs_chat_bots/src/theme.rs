@@ -0,0 +1,93 @@
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// The colors the views paint panels and status text with.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub header: Color,
+    pub panel: Color,
+    pub panel_alt: Color,
+    pub card: Color,
+    pub bubble: Color,
+    /// Chat rows for errors and for notices from the app.
+    pub bubble_error: Color,
+    pub bubble_info: Color,
+    pub highlight: Color,
+    pub text_muted: Color,
+    pub text_faint: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub caution: Color,
+    pub danger: Color,
+    pub error_text: Color,
+}
+
+const DARK: Palette = Palette {
+    background: Color::from_rgb(0.12, 0.12, 0.15),
+    header: Color::from_rgb(0.15, 0.15, 0.2),
+    panel: Color::from_rgb(0.18, 0.18, 0.22),
+    panel_alt: Color::from_rgb(0.2, 0.2, 0.25),
+    card: Color::from_rgb(0.22, 0.22, 0.26),
+    bubble: Color::from_rgb(0.25, 0.25, 0.3),
+    bubble_error: Color::from_rgb(0.38, 0.2, 0.22),
+    bubble_info: Color::from_rgb(0.2, 0.28, 0.38),
+    highlight: Color::from_rgb(0.28, 0.28, 0.32),
+    text_muted: Color::from_rgb(0.6, 0.6, 0.6),
+    text_faint: Color::from_rgb(0.5, 0.5, 0.5),
+    success: Color::from_rgb(0.2, 0.8, 0.3),
+    warning: Color::from_rgb(0.9, 0.7, 0.2),
+    caution: Color::from_rgb(0.9, 0.6, 0.2),
+    danger: Color::from_rgb(0.8, 0.2, 0.2),
+    error_text: Color::from_rgb(0.9, 0.4, 0.4),
+};
+
+const LIGHT: Palette = Palette {
+    background: Color::from_rgb(0.96, 0.96, 0.97),
+    header: Color::from_rgb(0.88, 0.88, 0.92),
+    panel: Color::from_rgb(0.92, 0.92, 0.94),
+    panel_alt: Color::from_rgb(0.9, 0.9, 0.93),
+    card: Color::from_rgb(0.94, 0.94, 0.96),
+    bubble: Color::from_rgb(1.0, 1.0, 1.0),
+    bubble_error: Color::from_rgb(0.99, 0.88, 0.88),
+    bubble_info: Color::from_rgb(0.87, 0.92, 0.99),
+    highlight: Color::from_rgb(0.84, 0.86, 0.92),
+    text_muted: Color::from_rgb(0.4, 0.4, 0.4),
+    text_faint: Color::from_rgb(0.5, 0.5, 0.5),
+    success: Color::from_rgb(0.1, 0.6, 0.2),
+    warning: Color::from_rgb(0.75, 0.55, 0.0),
+    caution: Color::from_rgb(0.8, 0.45, 0.1),
+    danger: Color::from_rgb(0.75, 0.15, 0.15),
+    error_text: Color::from_rgb(0.75, 0.2, 0.2),
+};
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => DARK,
+            Theme::Light => LIGHT,
+        }
+    }
+
+    /// The iced theme the built-in widgets are drawn with.
+    pub fn iced(self) -> iced::Theme {
+        match self {
+            Theme::Dark => iced::Theme::Dark,
+            Theme::Light => iced::Theme::Light,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Dark,
+        }
+    }
+}
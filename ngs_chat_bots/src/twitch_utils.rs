@@ -1,54 +1,605 @@
-use std::time::Duration;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::net::Shutdown;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, LazyLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use async_std::{
+    channel::{self, Receiver},
     io::{BufReader, WriteExt},
     net::TcpStream,
     prelude::*,
+    sync::{Mutex, MutexGuard},
 };
 use anyhow::Result;
+use futures_rustls::{TlsConnector, client::TlsStream, rustls};
+use iced::futures::io::{ReadHalf, WriteHalf};
+use serde::{Deserialize, Serialize};
+use crate::helix::TokenInfo;
+use crate::history::{ChatEntry, ChatLog};
 
 const SERVER: &str = "irc.chat.twitch.tv:6667";
+const SECURE_HOST: &str = "irc.chat.twitch.tv";
+const SECURE_SERVER: &str = "irc.chat.twitch.tv:6697";
 
-static BOT_COUNTER: AtomicUsize = AtomicUsize::new(1);
+pub const MAX_MESSAGE_LENGTH: usize = 500;
+
+/// Twitch lets an account send 20 messages per 30 seconds, or 100 in
+/// channels where it is a moderator.
+const RATE_WINDOW: Duration = Duration::from_secs(30);
+const USER_RATE_LIMIT: usize = 20;
+const MOD_RATE_LIMIT: usize = 100;
+
+/// Twitch lets an account whisper 3 times a second and 100 times a minute.
+const WHISPERS_PER_SECOND: usize = 3;
+const WHISPERS_PER_MINUTE: usize = 100;
+
+/// Network waits, in milliseconds. Kept in settings so they can be tuned
+/// for slow or fast connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+    /// The whole connection test, from connecting to the welcome line.
+    pub check_ms: u64,
+    /// The welcome line after logging in to send.
+    pub login_ms: u64,
+    /// The end of the NAMES list after a JOIN. Twitch takes PRIVMSGs right
+    /// after the JOIN, so a slow reply isn't an error.
+    pub join_ms: u64,
+    /// Pause between the parts of a split message.
+    pub part_gap_ms: u64,
+    /// Listening for a NOTICE rejecting a message after sending it.
+    pub notice_ms: u64,
+    /// Wait before the first retry of a failed send; doubles with each retry.
+    pub retry_backoff_ms: u64,
+}
+
+const DEFAULT_TIMEOUTS: Timeouts = Timeouts {
+    check_ms: 10_000,
+    login_ms: 5_000,
+    join_ms: 2_000,
+    part_gap_ms: 1_000,
+    notice_ms: 750,
+    retry_backoff_ms: 500,
+};
+
+/// No wait is set longer than this, which also keeps the retry backoff from
+/// overflowing as it doubles.
+pub const MAX_TIMEOUT_MS: u64 = 60_000;
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        DEFAULT_TIMEOUTS
+    }
+}
+
+impl Timeouts {
+    /// Every wait pulled into `1..=MAX_TIMEOUT_MS`, e.g. after a hand-edited
+    /// settings file.
+    pub fn clamped(self) -> Self {
+        let clamp = |ms: u64| ms.clamp(1, MAX_TIMEOUT_MS);
+        Self {
+            check_ms: clamp(self.check_ms),
+            login_ms: clamp(self.login_ms),
+            join_ms: clamp(self.join_ms),
+            part_gap_ms: clamp(self.part_gap_ms),
+            notice_ms: clamp(self.notice_ms),
+            retry_backoff_ms: clamp(self.retry_backoff_ms),
+        }
+    }
+}
+
+/// How many recent send results the health score looks at.
+const HEALTH_WINDOW: usize = 20;
+/// Sends at or under this count as fully fast; at `SLOW_SEND` they score zero.
+const FAST_SEND: Duration = Duration::from_secs(1);
+const SLOW_SEND: Duration = Duration::from_secs(10);
+
+/// Hands out bot ids, which also number the placeholder `bot_N` names.
+static BOT_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// Where a bot stands as of its last connection test.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BotStatus {
+    /// Not tested since it was loaded.
+    #[default]
+    Unknown,
+    /// A test is running or waiting for its turn.
+    Checking,
+    Available,
+    Unavailable,
+}
 
 #[derive(Clone, Debug)]
 pub struct Bot {
+    /// Stays the same for the bot's lifetime, unlike its position in the
+    /// list, so messages and state refer to bots by it.
+    pub id: u64,
     pub name: String,
+    pub login: String,
     pub token: String,
-    pub available: bool,
+    pub status: BotStatus,
     pub enable: bool,
-    pub chat_history: Vec<String>,
+    pub chat_history: ChatLog,
+    pub last_sent: Option<Instant>,
+    /// Chat scopes the token lacks, as of the last check.
+    pub missing_scopes: Vec<String>,
+    /// Sends go here instead of the global channel when set.
+    pub channel: Option<String>,
+    /// Uses the moderator rate limit instead of the normal one.
+    pub moderator: bool,
+    /// Milliseconds from connecting to the login welcome, as of the last
+    /// check.
+    pub ping_ms: Option<u64>,
+    /// The login Twitch welcomed the bot as on the last check, which can
+    /// differ from a mistyped config name.
+    pub resolved_name: Option<String>,
+    /// When the token stops working, as of the last check. `None` for
+    /// tokens that don't expire or weren't checked yet.
+    pub token_expires: Option<Instant>,
+    /// The name was made up because the config line didn't give one.
+    auto_named: bool,
+    /// Per-account budget over `RATE_WINDOW`, shared by the bot's clones.
+    rate_limit: Arc<std::sync::Mutex<BurstLimiter>>,
+    /// Whispers go over Helix, so they have their own limits instead of
+    /// counting against chat.
+    whisper_limits: [BurstLimiter; 2],
+    recent_results: VecDeque<bool>,
+    last_latency: Option<Duration>,
+    connection: SharedConnection,
+    inbox: Inbox,
+}
+
+fn rate_limit_capacity(moderator: bool) -> usize {
+    if moderator { MOD_RATE_LIMIT } else { USER_RATE_LIMIT }
+}
+
+/// A bot's IRC connection, opened on its first send and reused after.
+/// Clones of a bot share it.
+type SharedConnection = Arc<Mutex<Option<Connection>>>;
+
+/// Lowercased channels a connection is in. The reader forgets a channel
+/// the bot left or lost, so the next send there JOINs again.
+type JoinedChannels = Arc<std::sync::Mutex<HashSet<String>>>;
+
+/// Mentions the connection's reader picked up and the app hasn't shown yet.
+type Inbox = Arc<std::sync::Mutex<Vec<Mention>>>;
+
+/// A chat message in a joined channel that mentioned the bot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    pub channel: String,
+    pub sender: String,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct Connection {
+    writer: Arc<Mutex<IrcWriter>>,
+    /// Server replies the send path waits on; PINGs are answered by the reader.
+    replies: Receiver<String>,
+    joined: JoinedChannels,
+    secure: bool,
+}
+
+/// A bot's connection, held for one send attempt. Unless the attempt
+/// finishes cleanly the connection is dropped, also when the send task is
+/// aborted halfway, so the next send starts from a fresh login.
+struct SendSlot<'a> {
+    connection: MutexGuard<'a, Option<Connection>>,
+    clean: bool,
+}
+
+impl Drop for SendSlot<'_> {
+    fn drop(&mut self) {
+        if !self.clean {
+            *self.connection = None;
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Ends the reader task, which holds the other half of the socket.
+        let writer = self.writer.clone();
+        async_std::task::spawn(async move {
+            writer.lock().await.shutdown().await;
+        });
+    }
+}
+
+/// The read side of an IRC connection, plaintext or TLS.
+enum IrcReader {
+    Plain(BufReader<TcpStream>),
+    Tls(BufReader<ReadHalf<TlsStream<TcpStream>>>),
+}
+
+impl IrcReader {
+    async fn read_line(&mut self, line: &mut String) -> std::io::Result<usize> {
+        match self {
+            IrcReader::Plain(reader) => reader.read_line(line).await,
+            IrcReader::Tls(reader) => reader.read_line(line).await,
+        }
+    }
+}
+
+/// The write side of an IRC connection, plaintext or TLS.
+#[derive(Debug)]
+enum IrcWriter {
+    Plain(TcpStream),
+    Tls(WriteHalf<TlsStream<TcpStream>>),
+}
+
+impl IrcWriter {
+    async fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        match self {
+            IrcWriter::Plain(stream) => stream.write_all(bytes).await,
+            IrcWriter::Tls(stream) => stream.write_all(bytes).await,
+        }
+    }
+
+    async fn shutdown(&mut self) {
+        match self {
+            IrcWriter::Plain(stream) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            IrcWriter::Tls(stream) => {
+                let _ = iced::futures::AsyncWriteExt::close(stream).await;
+            }
+        }
+    }
+}
+
+/// TLS for IRC on rustls with the bundled web roots, the same stack reqwest
+/// uses for Helix, so the app links a single TLS implementation.
+static TLS_CONNECTOR: LazyLock<TlsConnector> = LazyLock::new(|| {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions")
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+});
+
+/// Opens a connection to Twitch IRC, over TLS on port 6697 when `tls` is
+/// set and plaintext 6667 otherwise.
+async fn open_stream(tls: bool) -> Result<(IrcReader, IrcWriter)> {
+    if !tls {
+        let stream = TcpStream::connect(SERVER).await?;
+        return Ok((IrcReader::Plain(BufReader::new(stream.clone())), IrcWriter::Plain(stream)));
+    }
+
+    let stream = TcpStream::connect(SECURE_SERVER).await?;
+    let stream = TLS_CONNECTOR.connect(SECURE_HOST.try_into()?, stream).await?;
+    let (reader, writer) = iced::futures::AsyncReadExt::split(stream);
+    Ok((IrcReader::Tls(BufReader::new(reader)), IrcWriter::Tls(writer)))
+}
+
+/// Weights of the two parts of a bot's health score.
+///
+/// The score is `0` for a bot that is disabled or failed its check. Otherwise
+/// it is `100 * (success * success_rate + latency * speed) / (success + latency)`,
+/// where `success_rate` is the share of the last 20 sends that went through
+/// and `speed` falls linearly from 1 at a 1s send to 0 at 10s. A bot with no
+/// sends yet scores 100.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HealthWeights {
+    pub success: f32,
+    pub latency: f32,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self { success: 0.7, latency: 0.3 }
+    }
+}
+
+/// The login and token of a bot, all a send or check task needs, plus a
+/// handle to its shared connection.
+#[derive(Clone)]
+pub struct BotCredentials {
+    pub login: String,
+    pub token: String,
+    moderator: bool,
+    rate_limit: Arc<std::sync::Mutex<BurstLimiter>>,
+    connection: SharedConnection,
+    inbox: Inbox,
+    timeouts: Timeouts,
+    tls: bool,
+}
+
+impl BotCredentials {
+    /// Logs in and times the round trip to the welcome, or gives `None` if
+    /// the login was refused.
+    pub async fn test_connection(&self) -> Result<Option<LoginCheck>> {
+        test_irc_connection(&self.login, &self.token, self.timeouts, self.tls).await
+    }
+
+    /// Looks the token up on Twitch's validate endpoint for its real login,
+    /// scopes and remaining lifetime.
+    pub async fn validate_token(&self) -> Result<TokenInfo> {
+        crate::helix::validate_token(&self.token).await
+    }
+
+    /// Whispers over Helix; IRC `/w` is no longer delivered by Twitch.
+    pub async fn send_whisper(&self, target: &str, message: &str) -> Result<()> {
+        crate::helix::send_whisper(&self.token, target, message).await
+    }
+
+    /// Deletes one of the bot's messages as a moderator, over Helix since
+    /// IRC `/delete` no longer works.
+    pub async fn delete_message(&self, channel: &str, message_id: &str) -> Result<()> {
+        crate::helix::delete_chat_message(&self.token, channel, message_id).await
+    }
+
+    /// Sends each `(channel, parts)` in turn, retrying up to `max_retries`
+    /// times with exponential backoff. A retry reconnects and picks up at
+    /// the first part that was not sent yet. The channels share the retry
+    /// budget. Reports how many retries were needed and how long it took.
+    pub async fn send_message(&self, payloads: &[(String, Vec<String>)], max_retries: u32) -> Result<Delivery> {
+        if payloads.is_empty() {
+            return Err(anyhow::anyhow!("No channel to send to"));
+        }
+
+        let started = Instant::now();
+        let mut retries = 0;
+        let mut message_ids = Vec::new();
+        for (channel, parts) in payloads {
+            let mut sent = Vec::new();
+            loop {
+                // Waits out the rate limit before taking the connection, so
+                // the bot's other sends aren't stuck behind the sleep.
+                let reserved = parts.len() - sent.len();
+                self.wait_for_rate_limit(reserved).await;
+                let before = sent.len();
+                let result = self.send_once(channel, parts, &mut sent).await;
+                if result.is_err() {
+                    self.release_rate_limit(reserved - (sent.len() - before));
+                }
+
+                match result {
+                    Ok(()) => {
+                        message_ids.extend(sent.into_iter().flatten().map(|id| (channel.clone(), id)));
+                        break;
+                    }
+                    // Twitch refusing the message won't change on a retry.
+                    Err(e) if retries < max_retries && e.downcast_ref::<ChatNotice>().is_none() => {
+                        retries += 1;
+                        let backoff = Duration::from_millis(self.timeouts.retry_backoff_ms)
+                            .saturating_mul(2u32.saturating_pow(retries - 1));
+                        log::warn!(
+                            "Send as {} failed: {}. Retrying ({}/{}) in {:?}",
+                            self.login, e, retries, max_retries, backoff
+                        );
+                        async_std::task::sleep(backoff).await;
+                    }
+                    Err(e) if retries > 0 => return Err(anyhow::anyhow!("{} (after {} retries)", e, retries)),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(Delivery {
+            retries,
+            latency: started.elapsed(),
+            message_ids,
+        })
+    }
+
+    /// Sends over the bot's open connection, connecting first if there is
+    /// none or the server closed it. A failed or aborted send drops the
+    /// connection so the next one starts fresh.
+    async fn send_once(&self, channel: &str, parts: &[String], sent: &mut Vec<Option<String>>) -> Result<()> {
+        let mut slot = SendSlot {
+            connection: self.connection.lock().await,
+            clean: false,
+        };
+        if slot.connection.as_ref().is_some_and(|c| c.replies.is_closed() || c.secure != self.tls) {
+            *slot.connection = None;
+        }
+
+        let open = match slot.connection.take() {
+            Some(open) => open,
+            None => connect(&self.login, &self.token, self.inbox.clone(), self.timeouts, self.tls).await?,
+        };
+        send_message_to_channel(slot.connection.insert(open), channel, &parts[sent.len()..], self, sent).await?;
+        slot.clean = true;
+        Ok(())
+    }
 }
 
 impl Bot {
     pub fn new(name: String, token: String) -> Self {
         Self {
+            id: BOT_COUNTER.fetch_add(1, Ordering::SeqCst),
+            login: name.clone(),
             name,
             token,
-            available: false,
+            status: BotStatus::Unknown,
             enable: true,
-            chat_history: Vec::new(),
+            chat_history: ChatLog::default(),
+            last_sent: None,
+            missing_scopes: Vec::new(),
+            channel: None,
+            moderator: false,
+            ping_ms: None,
+            resolved_name: None,
+            token_expires: None,
+            auto_named: false,
+            rate_limit: Arc::new(std::sync::Mutex::new(BurstLimiter::account())),
+            whisper_limits: [
+                BurstLimiter::always_on(WHISPERS_PER_SECOND, 1),
+                BurstLimiter::always_on(WHISPERS_PER_MINUTE, 60),
+            ],
+            recent_results: VecDeque::new(),
+            last_latency: None,
+            connection: SharedConnection::default(),
+            inbox: Inbox::default(),
+        }
+    }
+
+    /// A bot named `bot_<id>` until its token is checked.
+    fn auto_named(token: String) -> Self {
+        let mut bot = Self::new(String::new(), token);
+        bot.name = format!("bot_{}", bot.id);
+        bot.login = bot.name.clone();
+        bot.auto_named = true;
+        bot
+    }
+
+    /// A cheap copy of what a connection needs, for moving into tasks
+    /// without cloning the chat history.
+    /// Waits in the returned credentials' connection steps follow `timeouts`,
+    /// and new connections use TLS when `tls` is set. An open connection
+    /// made the other way is replaced on the next send.
+    pub fn credentials(&self, timeouts: Timeouts, tls: bool) -> BotCredentials {
+        BotCredentials {
+            login: self.login.clone(),
+            token: self.token.clone(),
+            moderator: self.moderator,
+            rate_limit: self.rate_limit.clone(),
+            connection: self.connection.clone(),
+            inbox: self.inbox.clone(),
+            timeouts,
+            tls,
+        }
+    }
+
+    /// Takes the mentions seen since the last call.
+    pub fn take_mentions(&self) -> Vec<Mention> {
+        self.inbox.lock().map(|mut inbox| std::mem::take(&mut *inbox)).unwrap_or_default()
+    }
+
+    /// How many messages the bot can send right now without waiting on its
+    /// rate limit.
+    pub fn available_sends(&self) -> usize {
+        self.rate_limit.lock().map_or(0, |mut limiter| {
+            limiter.max_messages = rate_limit_capacity(self.moderator);
+            limiter.available()
+        })
+    }
+
+    /// Takes a slot under both whisper limits, or returns how long until
+    /// one would fit.
+    pub fn acquire_whisper(&mut self) -> Result<(), Duration> {
+        let wait = self.whisper_limits
+            .iter_mut()
+            .filter_map(|limiter| match limiter.available() {
+                0 => limiter.acquire(1).err(),
+                _ => None,
+            })
+            .max();
+        match wait {
+            Some(wait) => Err(wait),
+            None => {
+                self.whisper_limits.iter_mut().for_each(|limiter| { limiter.try_acquire(1); });
+                Ok(())
+            },
         }
     }
 
-    pub async fn test_connection(&self) -> Result<bool> {
-        test_irc_connection(&self.name, &self.token).await
+    /// Lowercases the IRC login, keeping `name` as written for display.
+    pub fn normalize_login(&mut self) {
+        self.login = self.login.to_lowercase();
     }
 
-    pub async fn send_message(&self, channel: &str, message: &str) -> Result<()> {
-        send_message_to_channel(&self.name, &self.token, channel, message).await
+    pub fn masked_token(&self) -> String {
+        mask_token(&self.token)
     }
 
     pub fn set_available(&mut self, available: bool) {
-        self.available = available;
+        self.status = if available { BotStatus::Available } else { BotStatus::Unavailable };
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.status == BotStatus::Available
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enable = enabled;
     }
 
-    pub fn add_to_history(&mut self, message: String) {
-        self.chat_history.push(message);
+    pub fn add_to_history(&mut self, entry: ChatEntry) {
+        self.chat_history.push(entry);
+    }
+
+    /// Takes the login, scopes and expiry from a validated token. Bots that
+    /// weren't given a name in the config are renamed to their login.
+    pub fn apply_token_info(&mut self, info: &TokenInfo) {
+        self.login = info.login.clone();
+        if self.auto_named {
+            self.name = info.login.clone();
+            self.auto_named = false;
+        }
+        self.missing_scopes = info.missing_chat_scopes();
+        self.token_expires = (info.expires_in > 0)
+            .then(|| Instant::now() + Duration::from_secs(info.expires_in));
+    }
+
+    /// The config name, followed by the login Twitch knows the bot as when
+    /// the two don't match.
+    pub fn display_name(&self) -> String {
+        match &self.resolved_name {
+            Some(login) if !login.eq_ignore_ascii_case(&self.name) => format!("{} ({})", self.name, login),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// How long the token has left, if it expires within `window`.
+    pub fn expires_within(&self, window: Duration) -> Option<Duration> {
+        self.token_expires
+            .map(|expires| expires.saturating_duration_since(Instant::now()))
+            .filter(|left| *left < window)
+    }
+
+    /// Whether the bot posted less than `cooldown` ago.
+    pub fn is_cooling_down(&self, cooldown: Duration) -> bool {
+        self.last_sent.is_some_and(|sent| sent.elapsed() < cooldown)
+    }
+
+    pub fn record_sent(&mut self, entry: ChatEntry) {
+        self.last_sent = Some(Instant::now());
+        self.add_to_history(entry);
+    }
+
+    /// Records how a send finished, with how long it took when that was
+    /// measured.
+    pub fn record_result(&mut self, success: bool, latency: Option<Duration>) {
+        if self.recent_results.len() == HEALTH_WINDOW {
+            self.recent_results.pop_front();
+        }
+        self.recent_results.push_back(success);
+        if latency.is_some() {
+            self.last_latency = latency;
+        }
+    }
+
+    /// A 0–100 score from availability, recent success rate and latency.
+    /// See [`HealthWeights`] for the formula.
+    pub fn health_score(&self, weights: &HealthWeights) -> u8 {
+        if !self.is_available() || !self.enable {
+            return 0;
+        }
+
+        let success_rate = if self.recent_results.is_empty() {
+            1.0
+        } else {
+            self.recent_results.iter().filter(|&&ok| ok).count() as f32 / self.recent_results.len() as f32
+        };
+        let speed = self.last_latency.map_or(1.0, |latency| {
+            let over = latency.saturating_sub(FAST_SEND).as_secs_f32();
+            1.0 - (over / (SLOW_SEND - FAST_SEND).as_secs_f32()).min(1.0)
+        });
+
+        let total = weights.success.max(0.0) + weights.latency.max(0.0);
+        if total == 0.0 {
+            return 100;
+        }
+        let score = (weights.success.max(0.0) * success_rate + weights.latency.max(0.0) * speed) / total;
+        (score * 100.0).round() as u8
     }
 
     pub fn clear_history(&mut self) {
@@ -56,24 +607,121 @@ impl Bot {
     }
 }
 
+/// How a send that went out went: the retries it needed, the time from
+/// the first attempt to the last part written, and the `(channel, id)` of
+/// each part Twitch confirmed with an id.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub retries: u32,
+    pub latency: Duration,
+    pub message_ids: Vec<(String, String)>,
+}
+
+/// Sliding-window cap on PRIVMSGs: at most `max_messages` in any `window`.
+/// The app keeps one across all bots and channels, off until the user turns
+/// it on; each bot keeps an always-on one for Twitch's per-account limit.
+#[derive(Debug, Clone)]
+pub struct BurstLimiter {
+    pub enabled: bool,
+    pub max_messages: usize,
+    pub window: Duration,
+    sent: VecDeque<Instant>,
+}
+
+impl BurstLimiter {
+    pub fn new(max_messages: usize, window_secs: u64) -> Self {
+        Self {
+            enabled: false,
+            max_messages,
+            window: Duration::from_secs(window_secs),
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// The per-account limit, starting at the normal rate.
+    fn account() -> Self {
+        Self {
+            enabled: true,
+            max_messages: USER_RATE_LIMIT,
+            window: RATE_WINDOW,
+            sent: VecDeque::new(),
+        }
+    }
+
+    fn always_on(max_messages: usize, window_secs: u64) -> Self {
+        Self { enabled: true, ..Self::new(max_messages, window_secs) }
+    }
+
+    pub fn is_full(&mut self) -> bool {
+        self.prune(Instant::now());
+        self.enabled && self.sent.len() >= self.max_messages
+    }
+
+    /// Takes `slots` slots at once, or none if they don't all fit. A send
+    /// bigger than the whole limit still goes out once the window is empty.
+    pub fn try_acquire(&mut self, slots: usize) -> bool {
+        self.prune(Instant::now());
+        if self.enabled && !self.sent.is_empty() && self.sent.len() + slots > self.max_messages {
+            return false;
+        }
+        let now = Instant::now();
+        self.sent.extend(std::iter::repeat_n(now, slots));
+        true
+    }
+
+    /// How many slots are free right now.
+    pub fn available(&mut self) -> usize {
+        self.prune(Instant::now());
+        if !self.enabled {
+            return usize::MAX;
+        }
+        self.max_messages.saturating_sub(self.sent.len())
+    }
+
+    /// Takes `slots` slots, or returns how long until they would fit.
+    pub fn acquire(&mut self, slots: usize) -> Result<(), Duration> {
+        if self.try_acquire(slots) {
+            return Ok(());
+        }
+        // Enough of the oldest slots have to expire, or all of them for a
+        // send bigger than the whole limit.
+        let expiring = (self.sent.len() + slots - self.max_messages).min(self.sent.len());
+        let now = Instant::now();
+        let last = self.sent[expiring - 1];
+        Err(self.window.saturating_sub(now.duration_since(last)))
+    }
+
+    /// Gives back the newest `slots` slots, for sends that never went out.
+    pub fn release(&mut self, slots: usize) {
+        let kept = self.sent.len().saturating_sub(slots);
+        self.sent.truncate(kept);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some(&oldest) = self.sent.front() {
+            if now.duration_since(oldest) < self.window {
+                break;
+            }
+            self.sent.pop_front();
+        }
+    }
+}
+
 pub fn create_bots(content: &str) -> Vec<Bot> {
     content
         .lines()
         .filter_map(|line| {
             if let Some((token, name_part)) = line.split_once('|') {
                 let name = name_part.trim();
-                let final_name = if name.is_empty() {
-                    let id = BOT_COUNTER.fetch_add(1, Ordering::SeqCst);
-                    format!("bot_{}", id)
+                if name.is_empty() {
+                    Some(Bot::auto_named(token.trim().to_string()))
                 } else {
-                    name.to_string()
-                };
-                Some(Bot::new(final_name, token.trim().to_string()))
+                    Some(Bot::new(name.to_string(), token.trim().to_string()))
+                }
             } else {
                 let token = line.trim();
                 if !token.is_empty() {
-                    let id = BOT_COUNTER.fetch_add(1, Ordering::SeqCst);
-                    Some(Bot::new(format!("bot_{}", id), token.to_string()))
+                    Some(Bot::auto_named(token.to_string()))
                 } else {
                     None
                 }
@@ -82,93 +730,781 @@ pub fn create_bots(content: &str) -> Vec<Bot> {
         .collect()
 }
 
-async fn test_irc_connection(username: &str, oauth_token: &str) -> Result<bool> {
+/// One bot entry in a JSON or TOML config. Fields the app doesn't use, like
+/// notes, are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BotConfig {
+    pub token: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub moderator: bool,
+}
+
+impl BotConfig {
+    fn into_bot(self) -> Bot {
+        let token = self.token.trim().to_string();
+        let mut bot = match self.name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => Bot::new(name.to_string(), token),
+            _ => Bot::auto_named(token),
+        };
+        bot.channel = self
+            .channel
+            .map(|channel| channel.trim().trim_start_matches('#').to_string())
+            .filter(|channel| !channel.is_empty());
+        bot.moderator = self.moderator;
+        bot
+    }
+}
+
+/// TOML needs a table at the top, so bots go under `[[bots]]`.
+#[derive(Debug, Deserialize)]
+struct TomlBots {
+    #[serde(default)]
+    bots: Vec<BotConfig>,
+}
+
+/// Reads a JSON array of bot entries.
+pub fn create_bots_from_json(content: &str) -> Result<Vec<Bot>> {
+    let configs: Vec<BotConfig> = serde_json::from_str(content)?;
+    Ok(configs.into_iter().map(BotConfig::into_bot).collect())
+}
+
+/// Reads bot entries from the `[[bots]]` tables of a TOML file.
+pub fn create_bots_from_toml(content: &str) -> Result<Vec<Bot>> {
+    let file: TomlBots = toml::from_str(content)?;
+    Ok(file.bots.into_iter().map(BotConfig::into_bot).collect())
+}
+
+/// Picks the parser by file extension, falling back to `token|name` lines.
+pub fn load_bots(path: &Path, content: &str) -> Result<Vec<Bot>> {
+    match config_extension(path).as_deref() {
+        Some("json") => create_bots_from_json(content),
+        Some("toml") => create_bots_from_toml(content),
+        _ => Ok(create_bots(content)),
+    }
+}
+
+/// Whether the file is one line per token, which is the only format the
+/// app rewrites.
+pub fn is_line_config(path: &Path) -> bool {
+    !matches!(config_extension(path).as_deref(), Some("json" | "toml"))
+}
+
+fn config_extension(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Splits a comma-separated channel list, dropping `#`, blanks and
+/// duplicates. A single channel gives a list of one.
+pub fn parse_channels(channels: &str) -> Vec<String> {
+    let mut parsed: Vec<String> = Vec::new();
+    for channel in channels.split(',') {
+        let channel = channel.trim().trim_start_matches('#').trim();
+        if !channel.is_empty() && !parsed.iter().any(|seen| seen.eq_ignore_ascii_case(channel)) {
+            parsed.push(channel.to_string());
+        }
+    }
+    parsed
+}
+
+/// Twitch logins are 3 to 25 characters of lowercase letters, digits and
+/// underscores.
+pub fn is_valid_login(login: &str) -> bool {
+    (3..=25).contains(&login.len())
+        && login.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Masks an OAuth token for display and logs, keeping only the first and
+/// last two characters, e.g. `oauth:ab…yz`.
+pub fn mask_token(token: &str) -> String {
+    let token = token.trim_start_matches("oauth:");
+    let chars: Vec<char> = token.chars().collect();
+
+    if chars.len() <= 4 {
+        return "oauth:****".to_string();
+    }
+
+    format!(
+        "oauth:{}…{}",
+        chars[..2].iter().collect::<String>(),
+        chars[chars.len() - 2..].iter().collect::<String>()
+    )
+}
+
+/// Returns the action text of a `/me` message.
+/// A bare `/me` has nothing to act out and is sent as plain text.
+pub fn action_text(message: &str) -> Option<&str> {
+    let rest = message.trim_start().strip_prefix("/me")?;
+    if rest.starts_with(char::is_whitespace) && !rest.trim().is_empty() {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+/// Wraps text in a CTCP ACTION, which is how `/me` is sent over IRC.
+pub fn action_payload(text: &str) -> String {
+    format!("\x01ACTION {}\x01", text)
+}
+
+/// Returns the name of a slash command that IRC can't run. Twitch only
+/// handles `/me` over IRC; everything else (`/announce`, `/ban`, ...) would
+/// be posted as plain text.
+pub fn unsupported_command(message: &str) -> Option<&str> {
+    let command = message.trim_start().strip_prefix('/')?.split_whitespace().next()?;
+    (command != "me").then_some(command)
+}
+
+/// Whether `message` is a command IRC can't run and the user chose to
+/// reject those instead of posting them as text.
+pub fn is_rejected_command(message: &str, reject_unknown: bool) -> bool {
+    reject_unknown && unsupported_command(message).is_some()
+}
+
+/// Whether `message` is over Twitch's per-message limit.
+pub fn message_too_long(message: &str) -> bool {
+    message.chars().count() > MAX_MESSAGE_LENGTH
+}
+
+/// Splits a message into parts of at most `limit` characters, breaking only
+/// between words so mentions and URLs stay intact. A single word longer than
+/// the limit is split hard. With `numbered`, each part gets a ` (i/n)` suffix
+/// that is counted against the limit.
+pub fn split_message(message: &str, limit: usize, numbered: bool) -> Vec<String> {
+    let message = message.trim();
+    if message.chars().count() <= limit {
+        return vec![message.to_string()];
+    }
+
+    let mut total = 1;
+    loop {
+        let reserve = if numbered {
+            format!(" ({}/{})", total, total).chars().count()
+        } else {
+            0
+        };
+        let parts = pack_words(message, limit.saturating_sub(reserve).max(1));
+
+        if !numbered {
+            return parts;
+        }
+        if parts.len() <= total {
+            let count = parts.len();
+            return parts
+                .into_iter()
+                .enumerate()
+                .map(|(i, part)| format!("{} ({}/{})", part, i + 1, count))
+                .collect();
+        }
+        total = parts.len();
+    }
+}
+
+fn pack_words(message: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    for word in message.split_whitespace() {
+        let word_len = word.chars().count();
+
+        if word_len > limit {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            let chars: Vec<char> = word.chars().collect();
+            for chunk in chars.chunks(limit) {
+                if chunk.len() == limit {
+                    parts.push(chunk.iter().collect());
+                } else {
+                    current = chunk.iter().collect();
+                    current_len = chunk.len();
+                }
+            }
+            continue;
+        }
+
+        if current.is_empty() {
+            current.push_str(word);
+            current_len = word_len;
+        } else if current_len + 1 + word_len <= limit {
+            current.push(' ');
+            current.push_str(word);
+            current_len += 1 + word_len;
+        } else {
+            parts.push(std::mem::replace(&mut current, word.to_string()));
+            current_len = word_len;
+        }
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+/// A successful login test.
+#[derive(Debug, Clone)]
+pub struct LoginCheck {
+    /// Time from connecting to the welcome line.
+    pub ping: Duration,
+    /// The login named in the `001` welcome line.
+    pub login: Option<String>,
+}
+
+pub async fn test_irc_connection(username: &str, oauth_token: &str, timeouts: Timeouts, tls: bool) -> Result<Option<LoginCheck>> {
     let result = async_std::future::timeout(
-        Duration::from_secs(10),
+        Duration::from_millis(timeouts.check_ms),
         async {
-            let mut stream = TcpStream::connect(SERVER).await?;
+            let started = Instant::now();
+            let (mut reader, mut writer) = open_stream(tls).await?;
 
-            stream
+            writer
                 .write_all(format!("PASS oauth:{}\r\n", oauth_token).as_bytes())
                 .await?;
-            stream
+            writer
                 .write_all(format!("NICK {}\r\n", username).as_bytes())
                 .await?;
 
-            let mut buffer = vec![0u8; 2048];
-            let n = stream.read(&mut buffer).await?;
-            let response = String::from_utf8_lossy(&buffer[..n]);
-            
-            if response.contains(":tmi.twitch.tv 001") || response.contains("Welcome") {
-                anyhow::Ok(true)
-            } else if response.contains("Login authentication failed") 
-                || response.contains("Login unsuccessful") {
-                anyhow::Ok(false)
-            } else {
-                anyhow::Ok(false)
+            let mut line = String::new();
+            while reader.read_line(&mut line).await? > 0 {
+                if line.contains(":tmi.twitch.tv 001") || line.contains("Welcome") {
+                    return anyhow::Ok(Some(LoginCheck {
+                        ping: started.elapsed(),
+                        login: welcome_login(&line),
+                    }));
+                }
+                if line.contains("Login authentication failed")
+                    || line.contains("Login unsuccessful") {
+                    return anyhow::Ok(None);
+                }
+                line.clear();
             }
+            anyhow::Ok(None)
         }
     ).await;
 
     match result {
-        Ok(Ok(valid)) => Ok(valid),
-        _ => Ok(false),
+        Ok(Ok(ping)) => Ok(ping),
+        _ => Ok(None),
     }
 }
 
+/// Logs in and starts a reader task that answers PINGs for as long as the
+/// connection is open and puts mentions of the bot in `inbox`.
+async fn connect(nickname: &str, oauth: &str, inbox: Inbox, timeouts: Timeouts, tls: bool) -> Result<Connection> {
+    let (mut reader, writer) = open_stream(tls).await?;
+    let writer = Arc::new(Mutex::new(writer));
+    let (sender, replies) = channel::unbounded();
+    let joined = JoinedChannels::default();
+
+    let pong_writer = writer.clone();
+    let reader_joined = joined.clone();
+    let login = nickname.to_string();
+    async_std::task::spawn(async move {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line).await {
+                Ok(0) | Err(_) => {
+                    log::debug!("{}: connection closed", login);
+                    break;
+                }
+                Ok(_) => {}
+            }
+
+            // Twitch drops connections that leave a PING unanswered, so idle
+            // bots stay logged in only as long as this keeps replying.
+            if let Some(payload) = line.strip_prefix("PING") {
+                let pong = format!("PONG{}", payload);
+                if pong_writer.lock().await.write_all(pong.as_bytes()).await.is_err() {
+                    break;
+                }
+                log::debug!("{}: PING{} answered", login, payload.trim_end());
+                continue;
+            }
+
+            if let Some(channel) = lost_channel(&line, &login)
+                && let Ok(mut joined) = reader_joined.lock()
+                && joined.remove(&channel) {
+                log::debug!("{}: no longer counted as in #{}", login, channel);
+            }
+
+            // Other chat traffic from joined channels isn't needed; numerics,
+            // notices and USERSTATEs are what the send path waits on.
+            let command = irc_command(&line);
+            if command == "PRIVMSG" {
+                if let Some(mention) = parse_mention(&line, &login)
+                    && let Ok(mut inbox) = inbox.lock() {
+                    inbox.push(mention);
+                }
+                continue;
+            }
+            if (command.chars().all(|c| c.is_ascii_digit()) || command == "NOTICE" || command == "USERSTATE")
+                && sender.send(line.trim().to_string()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let connection = Connection {
+        writer,
+        replies,
+        joined,
+        secure: tls,
+    };
+
+    // Tags carry the msg-id of NOTICEs, which tell why a send was refused.
+    connection.write("CAP REQ :twitch.tv/tags twitch.tv/commands\r\n").await?;
+
+    connection.write(&format!("PASS oauth:{}\r\n", oauth)).await?;
+    // Not even the masked ends of the token go in the log file.
+    log::debug!("Sent: PASS oauth:***");
+
+    connection.write(&format!("NICK {}\r\n", nickname)).await?;
+    log::debug!("Sent: NICK {}", nickname);
+
+    let welcomed = connection
+        .wait_within(Duration::from_millis(timeouts.login_ms), |line| {
+            if line.contains("Login authentication failed") || line.contains("Login unsuccessful") {
+                Some(false)
+            } else {
+                line.contains(" 001 ").then_some(true)
+            }
+        })
+        .await;
+
+    match welcomed {
+        Some(true) => Ok(connection),
+        _ => Err(anyhow::anyhow!("Failed to log in as {}", nickname)),
+    }
+}
+
+impl Connection {
+    async fn write(&self, line: &str) -> Result<()> {
+        self.writer.lock().await.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn wait_within<T>(&self, timeout: Duration, mut check: impl FnMut(&str) -> Option<T>) -> Option<T> {
+        async_std::future::timeout(timeout, async {
+            while let Ok(line) = self.replies.recv().await {
+                log::debug!("< {}", line);
+                if let Some(value) = check(&line) {
+                    return Some(value);
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+impl BotCredentials {
+    /// Waits until the rate limit allows `slots` more messages, then takes them.
+    async fn wait_for_rate_limit(&self, slots: usize) {
+        loop {
+            let wait = match self.rate_limit.lock() {
+                Ok(mut limiter) => {
+                    limiter.max_messages = rate_limit_capacity(self.moderator);
+                    match limiter.acquire(slots) {
+                        Ok(()) => return,
+                        Err(wait) => wait,
+                    }
+                }
+                Err(_) => return,
+            };
+            log::info!("Rate-limited as {}, waiting {:.1}s", self.login, wait.as_secs_f32());
+            async_std::task::sleep(wait).await;
+        }
+    }
+
+    /// Gives back slots taken for messages that never went out.
+    fn release_rate_limit(&self, slots: usize) {
+        if let Ok(mut limiter) = self.rate_limit.lock() {
+            limiter.release(slots);
+        }
+    }
+}
+
+/// A message Twitch refused with a NOTICE, e.g. because the bot is banned or
+/// the chat is followers-only.
+#[derive(Debug, Clone)]
+pub struct ChatNotice {
+    pub msg_id: String,
+    pub text: String,
+}
+
+impl ChatNotice {
+    /// Reads a NOTICE sent to `channel`, ignoring any other line.
+    fn parse(line: &str, channel: &str) -> Option<Self> {
+        if irc_command(line) != "NOTICE" {
+            return None;
+        }
+
+        let (tags, rest) = split_tags(line);
+        let mut params = rest.splitn(3, ' ').skip(2);
+        let (target, text) = params.next()?.split_once(" :")?;
+        if !target.trim_start_matches('#').eq_ignore_ascii_case(channel) {
+            return None;
+        }
+
+        let msg_id = tags
+            .unwrap_or_default()
+            .split(';')
+            .find_map(|tag| tag.strip_prefix("msg-id="))
+            .unwrap_or_default();
+        Some(Self {
+            msg_id: msg_id.to_string(),
+            text: text.trim().to_string(),
+        })
+    }
+
+    /// Whether the notice means the bot is no longer in the channel.
+    fn loses_channel(&self) -> bool {
+        matches!(self.msg_id.as_str(), "msg_banned" | "msg_timedout" | "msg_suspended" | "msg_channel_suspended")
+    }
+
+    fn reason(&self) -> Option<&'static str> {
+        Some(match self.msg_id.as_str() {
+            "msg_banned" => "Banned from this channel",
+            "msg_timedout" => "Timed out",
+            "msg_channel_suspended" => "Channel is suspended",
+            "msg_followersonly" | "msg_followersonly_zero" | "msg_followersonly_followed" => "Followers-only chat",
+            "msg_subsonly" => "Subscribers-only chat",
+            "msg_emoteonly" => "Emote-only chat",
+            "msg_slowmode" => "Slow mode",
+            "msg_r9k" => "Unique-chat mode",
+            "msg_duplicate" => "Duplicate message",
+            "msg_ratelimit" => "Sending too fast",
+            "msg_verified_email" | "msg_requires_verified_phone_number" => "Account needs verification",
+            "msg_rejected" | "msg_rejected_mandatory" => "Blocked by AutoMod",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ChatNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason() {
+            Some(reason) => write!(f, "{}: {}", reason, self.text),
+            None if self.msg_id.is_empty() => write!(f, "Twitch refused the message: {}", self.text),
+            None => write!(f, "Twitch refused the message ({}): {}", self.msg_id, self.text),
+        }
+    }
+}
+
+impl std::error::Error for ChatNotice {}
+
+/// Splits the `@tags` off the front of an IRC line, if it has any.
+fn split_tags(line: &str) -> (Option<&str>, &str) {
+    match line.strip_prefix('@').and_then(|tagged| tagged.split_once(' ')) {
+        Some((tags, rest)) => (Some(tags), rest),
+        None => (None, line),
+    }
+}
+
+/// Reads a PRIVMSG that mentions `@login`, ignoring any other line.
+fn parse_mention(line: &str, login: &str) -> Option<Mention> {
+    let (_, rest) = split_tags(line.trim_end());
+    let (prefix, rest) = rest.strip_prefix(':')?.split_once(' ')?;
+    let (channel, text) = rest.strip_prefix("PRIVMSG #")?.split_once(" :")?;
+    let sender = prefix.split('!').next()?;
+
+    let mention = format!("@{}", login.to_lowercase());
+    text.to_lowercase().contains(&mention).then(|| Mention {
+        channel: channel.to_string(),
+        sender: sender.to_string(),
+        text: text.to_string(),
+    })
+}
+
+/// The lowercased channel a line says the bot left, was banned or timed out
+/// in, or got a ban, timeout or suspension NOTICE in. Any of these means it
+/// has to JOIN again before its next send there.
+fn lost_channel(line: &str, login: &str) -> Option<String> {
+    let line = line.trim_end();
+    let (_, rest) = split_tags(line);
+    let (prefix, rest) = match rest.strip_prefix(':') {
+        Some(prefixed) => prefixed.split_once(' ')?,
+        None => ("", rest),
+    };
+    let (command, params) = rest.split_once(' ')?;
+    let (target, trailing) = match params.split_once(" :") {
+        Some((target, trailing)) => (target, Some(trailing)),
+        None => (params, None),
+    };
+    let channel = target.trim().strip_prefix('#')?.to_lowercase();
+
+    let lost = match command {
+        "PART" => prefix.split('!').next().is_some_and(|nick| nick.eq_ignore_ascii_case(login)),
+        // A ban or timeout names the user; without one the whole chat was cleared.
+        "CLEARCHAT" => trailing.is_some_and(|user| user.trim().eq_ignore_ascii_case(login)),
+        // Other NOTICEs, like slow mode or a duplicate message, leave the bot in.
+        "NOTICE" => ChatNotice::parse(line, &channel).is_some_and(|notice| notice.loses_channel()),
+        _ => false,
+    };
+    lost.then_some(channel)
+}
+
+/// The channel of an end-of-names line, `:<login>.tmi.twitch.tv 366 <login> #<channel> :End of /NAMES list`.
+fn end_of_names_channel(line: &str) -> Option<&str> {
+    if irc_command(line) != "366" {
+        return None;
+    }
+    let (_, rest) = split_tags(line);
+    rest.split_whitespace()
+        .nth(3)
+        .and_then(|channel| channel.strip_prefix('#'))
+}
+
+/// The id of the bot's own message in a USERSTATE for `channel`. Twitch
+/// only tags the USERSTATE that follows a PRIVMSG with one, not the one
+/// after a JOIN.
+fn sent_message_id(line: &str, channel: &str) -> Option<String> {
+    if irc_command(line) != "USERSTATE" {
+        return None;
+    }
+    let (tags, rest) = split_tags(line.trim_end());
+    let target = rest.split_whitespace().nth(2)?;
+    if !target.trim_start_matches('#').eq_ignore_ascii_case(channel) {
+        return None;
+    }
+    tags?
+        .split(';')
+        .find_map(|tag| tag.strip_prefix("id="))
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+/// The command of an IRC line, e.g. `NOTICE` or `001`.
+fn irc_command(line: &str) -> &str {
+    let (_, rest) = split_tags(line);
+    let mut words = rest.split_whitespace();
+    if rest.starts_with(':') {
+        words.next();
+    }
+    words.next().unwrap_or_default()
+}
+
+/// The login in a welcome line, `:tmi.twitch.tv 001 <login> :Welcome, GLHF!`.
+fn welcome_login(line: &str) -> Option<String> {
+    if irc_command(line) != "001" {
+        return None;
+    }
+    let (_, rest) = split_tags(line);
+    rest.split_whitespace()
+        .nth(2)
+        .map(str::to_string)
+}
+
 async fn send_message_to_channel(
-    nickname: &str,
-    oauth: &str,
+    connection: &mut Connection,
     channel: &str,
-    message: &str,
+    parts: &[String],
+    credentials: &BotCredentials,
+    sent: &mut Vec<Option<String>>,
 ) -> Result<()> {
-    let stream = TcpStream::connect(SERVER).await?;
-    let (reader, mut writer) = (&stream, &stream);
-    
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
-    
-    writer.write_all(format!("PASS oauth:{}\n", oauth).as_bytes()).await?;
-    println!("Sent: PASS oauth:***");
-    
-    writer.write_all(format!("NICK {}\n", nickname).as_bytes()).await?;
-    println!("Sent: NICK {}", nickname);
-    
-    writer.write_all(format!("JOIN #{}\r\n", channel).as_bytes()).await?;
-    println!("Sent: JOIN #{}", channel);
-    
-    let timeout = std::time::Instant::now();
-    let mut joined = false;
-    
-    while timeout.elapsed() < Duration::from_secs(5) {
-        line.clear();
-        if let Ok(n) = reader.read_line(&mut line).await {
-            if n == 0 { break; }
-            println!("< {}", line.trim());
-            
-            if line.contains("366") || line.contains("End of /NAMES list") {
-                joined = true;
-                break;
-            }
-            
-            if line.starts_with("PING") {
-                let pong = line.replace("PING", "PONG");
-                writer.write_all(pong.as_bytes()).await?;
+    // Replies left over from earlier sends are stale.
+    while connection.replies.try_recv().is_ok() {}
+
+    let timeouts = credentials.timeouts;
+    let key = channel.to_lowercase();
+    let is_joined = connection.joined.lock().is_ok_and(|joined| joined.contains(&key));
+    if !is_joined {
+        connection.write(&format!("JOIN #{}\r\n", channel)).await?;
+        log::debug!("Sent: JOIN #{}", channel);
+
+        let joined = connection
+            .wait_within(Duration::from_millis(timeouts.join_ms), |line| {
+                if end_of_names_channel(line).is_some_and(|names| names.eq_ignore_ascii_case(channel)) {
+                    return Some(Ok(()));
+                }
+                ChatNotice::parse(line, channel).map(Err)
+            })
+            .await;
+
+        match joined {
+            // Keeps the NOTICE underneath, so the send still isn't retried.
+            Some(Err(notice)) => {
+                let message = format!("Not in #{}: {}", channel, notice);
+                return Err(anyhow::Error::new(notice).context(message));
             }
+            Some(Ok(())) => log::info!("Joined #{}", channel),
+            // The JOIN went out on a working socket; a refused send will
+            // still show up as a NOTICE below.
+            None => log::warn!("No 366 for #{} within {}ms, sending anyway", channel, timeouts.join_ms),
+        }
+
+        // Joined once per connection, even if the reply was slow.
+        if let Ok(mut joined) = connection.joined.lock() {
+            joined.insert(key);
         }
     }
-    
-    if !joined {
-        return Err(anyhow::anyhow!("Не удалось войти в канал"));
+
+    for (i, message) in parts.iter().enumerate() {
+        if i > 0 {
+            async_std::task::sleep(Duration::from_millis(timeouts.part_gap_ms)).await;
+        }
+        connection.write(&format!("PRIVMSG #{} :{}\r\n", channel, message)).await?;
+        log::info!("Sent: PRIVMSG #{} :{}", channel, message);
+
+        // A refused message gets a NOTICE instead of an error, so without
+        // this it would count as sent. An accepted one gets a USERSTATE
+        // carrying its id, which ends the wait early.
+        let reply = connection
+            .wait_within(Duration::from_millis(timeouts.notice_ms), |line| {
+                if let Some(notice) = ChatNotice::parse(line, channel) {
+                    return Some(Err(notice));
+                }
+                sent_message_id(line, channel).map(Ok)
+            })
+            .await;
+        match reply {
+            Some(Err(notice)) => return Err(notice.into()),
+            Some(Ok(id)) => sent.push(Some(id)),
+            None => sent.push(None),
+        }
     }
 
-    async_std::task::sleep(Duration::from_secs(1)).await;
-    println!("\n✓ Успешно вошли в канал, отправляем сообщение...\n");
-    
-    writer.write_all(format!("PRIVMSG #{} :{}\r\n", channel, message).as_bytes()).await?;
-    println!("Sent: PRIVMSG #{} :{}", channel, message);
-    
-    async_std::task::sleep(Duration::from_secs(2)).await;
-    
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_keeps_mention_at_boundary_whole() {
+        let message = format!("{} @someone_long hello", "a".repeat(15));
+        let parts = split_message(&message, 20, false);
+
+        assert_eq!(parts, vec!["a".repeat(15), "@someone_long hello".to_string()]);
+        assert!(parts.iter().all(|part| part.chars().count() <= 20));
+    }
+
+    #[test]
+    fn split_breaks_word_longer_than_limit() {
+        let word = "x".repeat(25);
+        let parts = split_message(&format!("hi {}", word), 10, false);
+
+        assert_eq!(parts, vec!["hi", "xxxxxxxxxx", "xxxxxxxxxx", "xxxxx"]);
+    }
+
+    #[test]
+    fn me_becomes_action() {
+        let action = action_text("/me waves").map(action_payload);
+        assert_eq!(action.as_deref(), Some("\x01ACTION waves\x01"));
+    }
+
+    #[test]
+    fn bare_me_is_plain_text() {
+        assert_eq!(action_text("/me"), None);
+        assert_eq!(action_text("/me   "), None);
+        assert_eq!(action_text("/meow"), None);
+    }
+
+    #[test]
+    fn unknown_command_follows_setting() {
+        assert_eq!(unsupported_command("/ban someone"), Some("ban"));
+        assert_eq!(unsupported_command("/me waves"), None);
+        assert!(is_rejected_command("/ban someone", true));
+        assert!(!is_rejected_command("/ban someone", false));
+        assert!(!is_rejected_command("/me waves", true));
+        assert!(!is_rejected_command("hello", true));
+    }
+
+    #[test]
+    fn numbered_parts_fit_limit() {
+        let parts = split_message(&"word ".repeat(20), 30, true);
+
+        assert!(parts.last().unwrap().ends_with(&format!("({}/{})", parts.len(), parts.len())));
+        assert!(parts.iter().all(|part| part.chars().count() <= 30));
+    }
+
+    #[test]
+    fn privmsg_mentioning_bot_is_a_mention() {
+        let line = "@badges=;display-name=Viewer :viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #stream :hi @MyBot o/\r\n";
+
+        assert_eq!(parse_mention(line, "mybot"), Some(Mention {
+            channel: "stream".to_string(),
+            sender: "viewer".to_string(),
+            text: "hi @MyBot o/".to_string(),
+        }));
+        assert_eq!(parse_mention(line, "otherbot"), None);
+        assert_eq!(parse_mention(":tmi.twitch.tv NOTICE #stream :@mybot", "mybot"), None);
+    }
+
+    #[test]
+    fn account_limit_waits_instead_of_overfilling() {
+        let mut limiter = BurstLimiter::account();
+
+        assert_eq!(limiter.acquire(USER_RATE_LIMIT - 1), Ok(()));
+        assert_eq!(limiter.available(), 1);
+        assert!(limiter.acquire(2).is_err_and(|wait| wait > Duration::ZERO && wait <= RATE_WINDOW));
+        limiter.release(USER_RATE_LIMIT - 1);
+        assert_eq!(limiter.acquire(2), Ok(()));
+    }
+
+    #[test]
+    fn whispers_have_their_own_limit() {
+        let mut bot = Bot::new("mybot".to_string(), "token".to_string());
+
+        for _ in 0..WHISPERS_PER_SECOND {
+            assert_eq!(bot.acquire_whisper(), Ok(()));
+        }
+        assert!(bot.acquire_whisper().is_err_and(|wait| wait > Duration::ZERO && wait <= Duration::from_secs(1)));
+        assert_eq!(bot.whisper_limits[1].available(), WHISPERS_PER_MINUTE - WHISPERS_PER_SECOND);
+        assert_eq!(bot.available_sends(), USER_RATE_LIMIT);
+    }
+
+    #[test]
+    fn userstate_after_privmsg_carries_message_id() {
+        let after_send = "@badges=;color=;id=8f3c2a10-5b7e-4d0c-9a61-0e2f1c4b7d93;mod=1 :tmi.twitch.tv USERSTATE #Stream";
+        let after_join = "@badges=;color=;mod=1 :tmi.twitch.tv USERSTATE #stream";
+
+        assert_eq!(sent_message_id(after_send, "stream").as_deref(), Some("8f3c2a10-5b7e-4d0c-9a61-0e2f1c4b7d93"));
+        assert_eq!(sent_message_id(after_send, "other"), None);
+        assert_eq!(sent_message_id(after_join, "stream"), None);
+    }
+
+    #[test]
+    fn end_of_names_matches_whole_channel() {
+        let line = ":mybot.tmi.twitch.tv 366 mybot #Stream_Two :End of /NAMES list";
+
+        assert_eq!(end_of_names_channel(line), Some("Stream_Two"));
+        assert!(!end_of_names_channel(line).is_some_and(|names| names.eq_ignore_ascii_case("stream")));
+        assert_eq!(end_of_names_channel(":mybot.tmi.twitch.tv 353 mybot = #stream :mybot"), None);
+    }
+
+    #[test]
+    fn part_ban_and_notice_lose_the_channel() {
+        assert_eq!(lost_channel(":mybot!mybot@mybot.tmi.twitch.tv PART #Stream\r\n", "MyBot"), Some("stream".to_string()));
+        assert_eq!(lost_channel(":viewer!viewer@viewer.tmi.twitch.tv PART #stream", "mybot"), None);
+        assert_eq!(lost_channel("@ban-duration=600 :tmi.twitch.tv CLEARCHAT #stream :mybot", "mybot"), Some("stream".to_string()));
+        assert_eq!(lost_channel(":tmi.twitch.tv CLEARCHAT #stream :viewer", "mybot"), None);
+        assert_eq!(lost_channel(":tmi.twitch.tv CLEARCHAT #stream", "mybot"), None);
+        assert_eq!(lost_channel("@msg-id=msg_banned :tmi.twitch.tv NOTICE #stream :You are banned", "mybot"), Some("stream".to_string()));
+        assert_eq!(lost_channel(":tmi.twitch.tv NOTICE * :Login unsuccessful", "mybot"), None);
+    }
+
+    #[test]
+    fn only_ban_timeout_and_suspension_notices_lose_the_channel() {
+        for msg_id in ["msg_banned", "msg_timedout", "msg_suspended", "msg_channel_suspended"] {
+            let line = format!("@msg-id={} :tmi.twitch.tv NOTICE #Stream :Nope\r\n", msg_id);
+            assert_eq!(lost_channel(&line, "mybot"), Some("stream".to_string()), "{}", msg_id);
+        }
+        for msg_id in ["msg_slowmode", "msg_duplicate", "msg_followersonly", "msg_ratelimit"] {
+            let line = format!("@msg-id={} :tmi.twitch.tv NOTICE #stream :Nope", msg_id);
+            assert_eq!(lost_channel(&line, "mybot"), None, "{}", msg_id);
+        }
+        assert_eq!(lost_channel(":tmi.twitch.tv NOTICE #stream :No msg-id", "mybot"), None);
+    }
+}